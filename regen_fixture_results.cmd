@@ -7,3 +7,11 @@
 "%~dp0target\debug\atlasbuilder.exe" --meta-template "toml" --image-output "%~dp0test_fixtures/results/template_toml_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_toml_file_result/out.toml" "%~dp0test_fixtures\input\input1.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
 "%~dp0target\debug\atlasbuilder.exe" --meta-template "xml" --image-output "%~dp0test_fixtures/results/template_xml_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_xml_file_result/out.xml" "%~dp0test_fixtures\input\input1.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
 "%~dp0target\debug\atlasbuilder.exe" --meta-template "yaml" --image-output "%~dp0test_fixtures/results/template_yaml_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_yaml_file_result/out.yaml" "%~dp0test_fixtures\input\input1.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "libgdx" --image-output "%~dp0test_fixtures/results/template_libgdx_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_libgdx_file_result/out.atlas" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "cocos2d-plist" --image-output "%~dp0test_fixtures/results/template_cocos2d_plist_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_cocos2d_plist_file_result/out.plist" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "starling" --image-output "%~dp0test_fixtures/results/template_starling_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_starling_file_result/out.xml" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "spine" --image-output "%~dp0test_fixtures/results/template_spine_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_spine_file_result/out.atlas" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "dragonbones" --image-output "%~dp0test_fixtures/results/template_dragonbones_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_dragonbones_file_result/out.json" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "c-header" --image-output "%~dp0test_fixtures/results/template_c_header_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_c_header_file_result/out.h" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --meta-template "rust-module" --image-output "%~dp0test_fixtures/results/template_rust_module_file_result/out.png" --meta-output "%~dp0test_fixtures/results/template_rust_module_file_result/out.rs" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png"
+"%~dp0target\debug\atlasbuilder.exe" --algorithm guillotine --guillotine-heuristic best-short-side-fit --guillotine-split min-area --guillotine-merge --image-output "%~dp0test_fixtures/results/guillotine_file_result/out.png" --meta-output "%~dp0test_fixtures/results/guillotine_file_result/out.json" "%~dp0test_fixtures\input\input1_trim.png" "%~dp0test_fixtures\input\input2.png" "%~dp0test_fixtures\input\input3.png" "%~dp0test_fixtures\input\input4.png" "%~dp0test_fixtures\input\input5.png"
@@ -0,0 +1,288 @@
+use std::cmp;
+use super::shapes;
+use super::packer;
+
+// Rules used to choose which free rectangle a sub image is placed into
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum Heuristic {
+	BestAreaFit,
+	BestShortSideFit,
+	BestLongSideFit,
+	WorstAreaFit,
+	WorstShortSideFit,
+	WorstLongSideFit
+}
+
+impl Heuristic {
+	pub const ALL: [Heuristic; 6] = [
+		Heuristic::BestAreaFit,
+		Heuristic::BestShortSideFit,
+		Heuristic::BestLongSideFit,
+		Heuristic::WorstAreaFit,
+		Heuristic::WorstShortSideFit,
+		Heuristic::WorstLongSideFit
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			Heuristic::BestAreaFit => "best-area-fit",
+			Heuristic::BestShortSideFit => "best-short-side-fit",
+			Heuristic::BestLongSideFit => "best-long-side-fit",
+			Heuristic::WorstAreaFit => "worst-area-fit",
+			Heuristic::WorstShortSideFit => "worst-short-side-fit",
+			Heuristic::WorstLongSideFit => "worst-long-side-fit"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<Heuristic> {
+		Heuristic::ALL.iter().copied().find( |heuristic| heuristic.name() == name )
+	}
+}
+
+// Rules used to choose which way the leftover space in a free rectangle is split after a sub image is placed into it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitRule {
+	ShorterLeftoverAxis,
+	LongerLeftoverAxis,
+	MinimizeArea,
+	MaximizeArea,
+	ShorterAxis,
+	LongerAxis
+}
+
+impl SplitRule {
+	pub const ALL: [SplitRule; 6] = [
+		SplitRule::ShorterLeftoverAxis,
+		SplitRule::LongerLeftoverAxis,
+		SplitRule::MinimizeArea,
+		SplitRule::MaximizeArea,
+		SplitRule::ShorterAxis,
+		SplitRule::LongerAxis
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			SplitRule::ShorterLeftoverAxis => "shorter-leftover-axis",
+			SplitRule::LongerLeftoverAxis => "longer-leftover-axis",
+			SplitRule::MinimizeArea => "min-area",
+			SplitRule::MaximizeArea => "max-area",
+			SplitRule::ShorterAxis => "shorter-axis",
+			SplitRule::LongerAxis => "longer-axis"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<SplitRule> {
+		SplitRule::ALL.iter().copied().find( |split_rule| split_rule.name() == name )
+	}
+}
+
+// Guillotine bin packer. Each placement splits its free rectangle in two with a single edge to edge cut,
+// so the free rectangles never overlap
+pub struct GuillotineBin {
+	heuristic: Heuristic,
+	split_rule: SplitRule,
+	merge: bool,
+	free_rects: Vec<shapes::Rect>
+}
+
+impl GuillotineBin {
+	pub fn new( bounds: shapes::Rect, heuristic: Heuristic, split_rule: SplitRule, merge: bool ) -> GuillotineBin {
+		GuillotineBin{
+			heuristic,
+			split_rule,
+			merge,
+			free_rects: vec![ bounds ]
+		}
+	}
+
+	// Scores placing a w x h rect into free_rect using the selected heuristic. Lower scores are better
+	fn score_placement( &self, free_rect: &shapes::Rect, w: i32, h: i32 ) -> i32 {
+		let area_fit = free_rect.w * free_rect.h - w * h;
+		let leftover_horiz = ( free_rect.w - w ).abs();
+		let leftover_vert = ( free_rect.h - h ).abs();
+		let short_side_fit = cmp::min( leftover_horiz, leftover_vert );
+		let long_side_fit = cmp::max( leftover_horiz, leftover_vert );
+		match self.heuristic {
+			Heuristic::BestAreaFit => area_fit,
+			Heuristic::BestShortSideFit => short_side_fit,
+			Heuristic::BestLongSideFit => long_side_fit,
+			Heuristic::WorstAreaFit => -area_fit,
+			Heuristic::WorstShortSideFit => -short_side_fit,
+			Heuristic::WorstLongSideFit => -long_side_fit
+		}
+	}
+
+	fn find_best_free_rect( &self, w: i32, h: i32, allow_rotate: bool ) -> Option<( usize, packer::PackResult )> {
+		let mut best_score = i32::MAX;
+		let mut best: Option<( usize, packer::PackResult )> = None;
+		for ( index, free_rect ) in self.free_rects.iter().enumerate() {
+			if free_rect.w >= w && free_rect.h >= h {
+				let result = packer::PackResult{ rect: shapes::Rect{ x: free_rect.x, y: free_rect.y, w, h }, rotated: false, page: 0 };
+				if free_rect.w == w && free_rect.h == h {
+					// perfect fit, can't do any better than that
+					return Some( ( index, result ) );
+				}
+				let score = self.score_placement( free_rect, w, h );
+				if score < best_score {
+					best_score = score;
+					best = Some( ( index, result ) );
+				}
+			}
+			if allow_rotate && free_rect.w >= h && free_rect.h >= w {
+				let result = packer::PackResult{ rect: shapes::Rect{ x: free_rect.x, y: free_rect.y, w: h, h: w }, rotated: true, page: 0 };
+				if free_rect.w == h && free_rect.h == w {
+					return Some( ( index, result ) );
+				}
+				let score = self.score_placement( free_rect, h, w );
+				if score < best_score {
+					best_score = score;
+					best = Some( ( index, result ) );
+				}
+			}
+		}
+		best
+	}
+
+	// Splits the space left over in free_rect after placed has been put into its top left corner into two new free rects
+	fn split_free_rect( &mut self, free_rect: &shapes::Rect, placed: &shapes::Rect ) {
+		let leftover_w = free_rect.w - placed.w;
+		let leftover_h = free_rect.h - placed.h;
+		let split_horizontal = match self.split_rule {
+			SplitRule::ShorterLeftoverAxis => leftover_w <= leftover_h,
+			SplitRule::LongerLeftoverAxis => leftover_w > leftover_h,
+			SplitRule::MinimizeArea => placed.w * leftover_h > leftover_w * placed.h,
+			SplitRule::MaximizeArea => placed.w * leftover_h <= leftover_w * placed.h,
+			SplitRule::ShorterAxis => free_rect.w <= free_rect.h,
+			SplitRule::LongerAxis => free_rect.w > free_rect.h
+		};
+
+		// a horizontal split gives the full width of free_rect to the bottom rect, a vertical split gives the full height to the right rect
+		let bottom = shapes::Rect{
+			x: free_rect.x,
+			y: free_rect.y + placed.h,
+			w: if split_horizontal { free_rect.w } else { placed.w },
+			h: leftover_h
+		};
+		let right = shapes::Rect{
+			x: free_rect.x + placed.w,
+			y: free_rect.y,
+			w: leftover_w,
+			h: if split_horizontal { placed.h } else { free_rect.h }
+		};
+		if bottom.w > 0 && bottom.h > 0 {
+			self.free_rects.push( bottom );
+		}
+		if right.w > 0 && right.h > 0 {
+			self.free_rects.push( right );
+		}
+	}
+
+	// Joins together pairs of free rects which share a complete edge
+	fn merge_free_rects( &mut self ) {
+		let mut i = 0;
+		while i < self.free_rects.len() {
+			let mut j = i + 1;
+			while j < self.free_rects.len() {
+				let b = self.free_rects[j];
+				let a = &mut self.free_rects[i];
+				let mut merged = true;
+				if a.w == b.w && a.x == b.x && a.y == b.y + b.h {
+					a.y -= b.h;
+					a.h += b.h;
+				} else if a.w == b.w && a.x == b.x && a.y + a.h == b.y {
+					a.h += b.h;
+				} else if a.h == b.h && a.y == b.y && a.x == b.x + b.w {
+					a.x -= b.w;
+					a.w += b.w;
+				} else if a.h == b.h && a.y == b.y && a.x + a.w == b.x {
+					a.w += b.w;
+				} else {
+					merged = false;
+				}
+				if merged {
+					self.free_rects.remove( j );
+				} else {
+					j += 1;
+				}
+			}
+			i += 1;
+		}
+	}
+}
+
+impl packer::BinPacker for GuillotineBin {
+	fn insert( &mut self, w: i32, h: i32, allow_rotate: bool ) -> Option<packer::PackResult> {
+		let ( index, result ) = self.find_best_free_rect( w, h, allow_rotate )?;
+		let free_rect = self.free_rects.remove( index );
+		self.split_free_rect( &free_rect, &result.rect );
+		if self.merge {
+			self.merge_free_rects();
+		}
+		Some( result )
+	}
+}
+
+#[cfg(test)]
+mod test_guillotine {
+	use super::packer::BinPacker;
+
+	fn assert_rect( rect: &super::shapes::Rect, x: i32, y: i32, w: i32, h: i32, message: &str ) {
+		assert_eq!( rect.x, x, "{} - x", message );
+		assert_eq!( rect.y, y, "{} - y", message );
+		assert_eq!( rect.w, w, "{} - w", message );
+		assert_eq!( rect.h, h, "{} - h", message );
+	}
+
+	#[test]
+	fn split_test() {
+		let bounds = super::shapes::Rect{ x: 0, y: 0, w: 100, h: 100 };
+
+		// leftover is 70 wide and 40 high, so the shorter leftover axis rule splits vertically
+		let mut bin = super::GuillotineBin::new( bounds, super::Heuristic::BestAreaFit, super::SplitRule::ShorterLeftoverAxis, false );
+		assert_rect( &bin.insert( 30, 60, false ).unwrap().rect, 0, 0, 30, 60, "Shorter leftover axis" );
+		assert_eq!( bin.free_rects.len(), 2 );
+		assert_rect( &bin.free_rects[0], 0, 60, 30, 40, "Bottom" );
+		assert_rect( &bin.free_rects[1], 30, 0, 70, 100, "Right" );
+
+		let mut bin = super::GuillotineBin::new( bounds, super::Heuristic::BestAreaFit, super::SplitRule::LongerLeftoverAxis, false );
+		bin.insert( 30, 60, false ).unwrap();
+		assert_rect( &bin.free_rects[0], 0, 60, 100, 40, "Bottom" );
+		assert_rect( &bin.free_rects[1], 30, 0, 70, 60, "Right" );
+	}
+
+	#[test]
+	fn heuristic_test() {
+		let bounds = super::shapes::Rect{ x: 0, y: 0, w: 100, h: 100 };
+		let mut bin = super::GuillotineBin::new( bounds, super::Heuristic::BestAreaFit, super::SplitRule::LongerLeftoverAxis, false );
+		bin.insert( 30, 60, false ).unwrap();
+		// free space is 100x40 along the bottom and 70x60 to the right
+		assert_rect( &bin.insert( 20, 20, false ).unwrap().rect, 0, 60, 20, 20, "Best area fit" );
+
+		let mut bin = super::GuillotineBin::new( bounds, super::Heuristic::WorstAreaFit, super::SplitRule::LongerLeftoverAxis, false );
+		bin.insert( 30, 60, false ).unwrap();
+		assert_rect( &bin.insert( 20, 20, false ).unwrap().rect, 30, 0, 20, 20, "Worst area fit" );
+	}
+
+	#[test]
+	fn merge_test() {
+		let bounds = super::shapes::Rect{ x: 0, y: 0, w: 100, h: 100 };
+		let free_rects = vec![
+			super::shapes::Rect{ x: 0, y: 50, w: 50, h: 50 },
+			super::shapes::Rect{ x: 50, y: 50, w: 50, h: 50 },
+			super::shapes::Rect{ x: 0, y: 0, w: 20, h: 20 }
+		];
+
+		let mut bin = super::GuillotineBin::new( bounds, super::Heuristic::BestAreaFit, super::SplitRule::ShorterAxis, false );
+		bin.free_rects = free_rects.clone();
+		assert_rect( &bin.insert( 20, 20, false ).unwrap().rect, 0, 0, 20, 20, "Perfect fit" );
+		assert!( bin.insert( 100, 50, false ).is_none() );
+
+		let mut bin = super::GuillotineBin::new( bounds, super::Heuristic::BestAreaFit, super::SplitRule::ShorterAxis, true );
+		bin.free_rects = free_rects;
+		assert_rect( &bin.insert( 20, 20, false ).unwrap().rect, 0, 0, 20, 20, "Perfect fit" );
+		assert_eq!( bin.free_rects.len(), 1 );
+		assert_rect( &bin.free_rects[0], 0, 50, 100, 50, "Merged" );
+		assert_rect( &bin.insert( 100, 50, false ).unwrap().rect, 0, 50, 100, 50, "Merged placement" );
+	}
+}
@@ -1,5 +1,68 @@
 extern crate image;
 
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+
+// How transparent edges are removed from sub images
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrimMode {
+	// keep the whole image
+	None,
+	// remove transparent edges, the meta data reports the original size and the offset of the trimmed area
+	Trim,
+	// remove transparent edges, the meta data reports the trimmed size as if the image was never any larger
+	Crop,
+	// as crop, but the meta data keeps the offset of the trimmed area
+	CropKeepPos
+}
+
+impl TrimMode {
+	pub const ALL: [TrimMode; 4] = [
+		TrimMode::None,
+		TrimMode::Trim,
+		TrimMode::Crop,
+		TrimMode::CropKeepPos
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			TrimMode::None => "none",
+			TrimMode::Trim => "trim",
+			TrimMode::Crop => "crop",
+			TrimMode::CropKeepPos => "crop-keep-pos"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<TrimMode> {
+		TrimMode::ALL.iter().copied().find( |trim_mode| trim_mode.name() == name )
+	}
+}
+
+// Widths in pixels of the fixed size edges of a nine-slice sub image. The area between them is stretched
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NineSlice {
+	pub left: i32,
+	pub top: i32,
+	pub right: i32,
+	pub bottom: i32
+}
+
+// True if the pixel is an opaque black Android nine-patch guide pixel
+fn is_guide_pixel( pixel: &[u8] ) -> bool {
+	pixel == [ 0, 0, 0, 255 ]
+}
+
+// Finds the first and last guide pixel in a row or column of the guide border, returning the lengths of the fixed areas before
+// and after them. Excludes the corner pixels, so the lengths are relative to the image without the guide border
+fn guide_extent( pixels: &[&[u8]] ) -> Option<( i32, i32 )> {
+	let inner = &pixels[1..pixels.len() - 1];
+	let first = inner.iter().position( |pixel| is_guide_pixel( pixel ) )?;
+	let last = inner.iter().rposition( |pixel| is_guide_pixel( pixel ) )?;
+	Some( ( first as i32, ( inner.len() - last - 1 ) as i32 ) )
+}
+
 pub struct InputImage {
 	pub name: std::path::PathBuf,
 	pub data: Vec<u8>,
@@ -8,11 +71,90 @@ pub struct InputImage {
 	pub vx: i32, // trim coords
 	pub vy: i32,
 	pub vw: i32, // width and height after trimming
-	pub vh: i32
+	pub vh: i32,
+	pub trim_mode: TrimMode,
+	// convex polygon around the visible pixels, relative to the trimmed area. Empty unless polygon outlines are enabled
+	pub outline: Vec<( f32, f32 )>,
+	// pivot point in pixels relative to the top left of the original image
+	pub pivot: ( f32, f32 ),
+	pub nine_slice: Option<NineSlice>,
+	// true if the nine-slice borders were read from Android nine-patch guides, which have been removed from the image
+	pub nine_patch: bool,
+	// frames per second and looping of the animation this sub image is a frame of, if set in its sidecar file
+	pub fps: Option<f32>,
+	pub looping: Option<bool>
 }
 
 
 impl InputImage {
+	pub fn new( name: &std::path::Path, w: i32, h: i32, data: Vec<u8> ) -> InputImage {
+		InputImage {
+			name: name.to_owned(),
+			w,
+			h,
+			vw: w,
+			vh: h,
+			vx: 0,
+			vy: 0,
+			data,
+			trim_mode: TrimMode::None,
+			outline: vec!(),
+			pivot: ( w as f32 / 2.0, h as f32 / 2.0 ),
+			nine_slice: None,
+			nine_patch: false,
+			fps: None,
+			looping: None
+		}
+	}
+
+	// True if the filename follows the Android nine-patch naming convention, e.g. panel.9.png
+	fn is_nine_patch_name( filename: &std::path::Path ) -> bool {
+		filename.file_name().and_then( |name| name.to_str() ).is_some_and( |name| name.to_lowercase().ends_with( ".9.png" ) )
+	}
+
+	// Reads the nine-slice borders from the 1 pixel guide border of an Android nine-patch image, then removes the guide border.
+	// The top and left guides mark the stretchable area. Leaves the image untouched if it has no guides
+	fn strip_nine_patch_guides( &mut self ) {
+		if self.w < 3 || self.h < 3 {
+			return;
+		}
+		let pixel = |x: i32, y: i32| -> &[u8] {
+			let index = ( ( y * self.w + x ) * 4 ) as usize;
+			&self.data[index..index + 4]
+		};
+		let top_row: Vec<&[u8]> = ( 0..self.w ).map( |x| pixel( x, 0 ) ).collect();
+		let left_column: Vec<&[u8]> = ( 0..self.h ).map( |y| pixel( 0, y ) ).collect();
+		let ( left, right ) = match guide_extent( &top_row ) {
+			Some( extent ) => extent,
+			None => return
+		};
+		let ( top, bottom ) = match guide_extent( &left_column ) {
+			Some( extent ) => extent,
+			None => return
+		};
+		let w = self.w - 2;
+		let h = self.h - 2;
+		let mut data: Vec<u8> = Vec::with_capacity( ( w * h * 4 ) as usize );
+		for y in 1..self.h - 1 {
+			let start = ( ( y * self.w + 1 ) * 4 ) as usize;
+			data.extend_from_slice( &self.data[start..start + ( w * 4 ) as usize] );
+		}
+		*self = InputImage::new( &self.name, w, h, data );
+		self.nine_slice = Some( NineSlice{ left, top, right, bottom } );
+		self.nine_patch = true;
+	}
+
+	// Name of the sub image for the meta data. Nine-patch images lose their .9 suffix along with their guides, e.g. panel.9.png becomes panel.png
+	pub fn display_name( &self ) -> std::path::PathBuf {
+		if !self.nine_patch {
+			return self.name.clone();
+		}
+		match ( self.name.file_stem().and_then( |stem| stem.to_str() ), self.name.extension().and_then( |extension| extension.to_str() ) ) {
+			( Some( stem ), Some( extension ) ) if stem.len() > 2 && stem.ends_with( ".9" ) => self.name.with_file_name( format!( "{}.{}", &stem[..stem.len() - 2], extension ) ),
+			_ => self.name.clone()
+		}
+	}
+
 	pub fn load( filename: &std::path::PathBuf ) -> InputImage {
 		let imga = image::open( filename ).unwrap();
 		let img: image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>> = imga.into_rgba8();
@@ -20,65 +162,291 @@ impl InputImage {
 		let data = img.into_vec();
 		let w = dims.0 as i32;
 		let h = dims.1 as i32;
-		InputImage { name: filename.to_owned(), w: w, h: h, vw: w, vh: h, vx: 0, vy: 0, data: data }
+		let mut input = InputImage::new( filename, w, h, data );
+		if InputImage::is_nine_patch_name( filename ) {
+			input.strip_nine_patch_guides();
+		}
+		input
 	}
 	
-	pub fn trim( &mut self ) {
-		let mut left = 0;
-		let mut right = 0;
-		for row in 0..self.h {
-			// find leftmost pixel
+	// Iterates over each row of pixel data within the trimmed area
+	fn trimmed_rows( &self ) -> impl Iterator<Item = &[u8]> {
+		( self.vy..self.vy + self.vh ).map( move |row| {
+			let start = ( ( row * self.w + self.vx ) * 4 ) as usize;
+			&self.data[start..start + ( self.vw * 4 ) as usize]
+		} )
+	}
+
+	// Hash of the trimmed pixel data, used to quickly find duplicate sub images
+	pub fn trimmed_hash( &self ) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		self.vw.hash( &mut hasher );
+		self.vh.hash( &mut hasher );
+		for row in self.trimmed_rows() {
+			row.hash( &mut hasher );
+		}
+		hasher.finish()
+	}
+
+	// True if both images have identical trimmed pixel data
+	pub fn trimmed_eq( &self, other: &InputImage ) -> bool {
+		self.vw == other.vw && self.vh == other.vh && self.trimmed_rows().eq( other.trimmed_rows() )
+	}
+
+	// Bounds of the pixels with an alpha value above threshold as ( x, y, w, h ), or None if every pixel is transparent
+	fn opaque_bounds( &self, threshold: u8 ) -> Option<( i32, i32, i32, i32 )> {
+		let mut bounds: Option<( i32, i32, i32, i32 )> = None;
+		for y in 0..self.h {
 			for x in 0..self.w {
-				let alpha = self.data[((row*self.w + x) * 4 + 3) as usize];
-				if alpha != 0 {
-					if left == 0 || x < left {
-						left = x;
-					}
-					break;
+				let alpha = self.data[((y*self.w + x) * 4 + 3) as usize];
+				if alpha <= threshold {
+					continue;
 				}
+				bounds = Some( match bounds {
+					None => ( x, y, x, y ),
+					Some( ( left, top, right, bottom ) ) => ( cmp::min( left, x ), cmp::min( top, y ), cmp::max( right, x ), cmp::max( bottom, y ) )
+				} );
 			}
+		}
+		// right and bottom are inclusive
+		bounds.map( |( left, top, right, bottom )| ( left, top, right - left + 1, bottom - top + 1 ) )
+	}
 
-			// find rightmost pixel
-			for x in (0..self.w).rev() {
-				let alpha = self.data[((row*self.w + x) * 4 + 3) as usize];
-				if alpha != 0 {
-					if right == 0 || x > right {
-						right = x;
-					}
-					break;
-				}
+	// Trims transparent edges, treating pixels with alpha <= threshold as transparent.
+	// margin pixels are kept around the trimmed area where the image is large enough.
+	// Returns false if the image is completely transparent, in which case it is reduced to its top left pixel
+	pub fn trim( &mut self, mode: TrimMode, threshold: u8, margin: i32 ) -> bool {
+		self.trim_mode = mode;
+		let bounds = self.opaque_bounds( threshold );
+		if mode == TrimMode::None {
+			return bounds.is_some();
+		}
+		match bounds {
+			Some( ( x, y, w, h ) ) => {
+				self.vx = cmp::max( x - margin, 0 );
+				self.vy = cmp::max( y - margin, 0 );
+				self.vw = cmp::min( x + w + margin, self.w ) - self.vx;
+				self.vh = cmp::min( y + h + margin, self.h ) - self.vy;
+				true
+			},
+			None => {
+				self.vx = 0;
+				self.vy = 0;
+				self.vw = cmp::min( 1, self.w );
+				self.vh = cmp::min( 1, self.h );
+				false
 			}
 		}
+	}
+
+	// Whether the meta data should report this sub image as trimmed
+	pub fn is_trimmed( &self ) -> bool {
+		self.trim_mode == TrimMode::Trim && ( self.vx, self.vy, self.vw, self.vh ) != ( 0, 0, self.w, self.h )
+	}
+
+	// Size of the sub image reported in the meta data. Cropping discards the transparent edges entirely
+	pub fn source_size( &self ) -> ( i32, i32 ) {
+		match self.trim_mode {
+			TrimMode::Crop | TrimMode::CropKeepPos => ( self.vw, self.vh ),
+			TrimMode::None | TrimMode::Trim => ( self.w, self.h )
+		}
+	}
 
-		let mut top = 0;
-		let mut bottom = 0;
-		for col in 0..self.w {
-			// find topmost pixel
-			for y in 0..self.h {
-				let alpha = self.data[((y*self.w + col) * 4 + 3) as usize];
-				if alpha != 0 {
-					if top == 0 || y < top {
-						top = y;
-					}
-					break;
-				}
-			}
+	// Top left of the area reported as the sub image in the meta data, relative to the original image
+	pub fn source_origin( &self ) -> ( i32, i32 ) {
+		match self.trim_mode {
+			TrimMode::Crop | TrimMode::CropKeepPos => ( self.vx, self.vy ),
+			TrimMode::None | TrimMode::Trim => ( 0, 0 )
+		}
+	}
 
-			// find bottommost pixel
-			for y in (0..self.h).rev() {
-				let alpha = self.data[((y*self.w + col) * 4 + 3) as usize];
-				if alpha != 0 {
-					if bottom == 0 || y > bottom {
-						bottom = y;
-					}
-					break;
-				}
+	// Offset of the trimmed area reported in the meta data
+	pub fn source_offset( &self ) -> ( i32, i32 ) {
+		match self.trim_mode {
+			TrimMode::Crop => ( 0, 0 ),
+			TrimMode::None | TrimMode::Trim | TrimMode::CropKeepPos => ( self.vx, self.vy )
+		}
+	}
+}
+
+// Separates out sub images whose trimmed pixels are identical to an earlier sub image, so they only need to be packed once.
+// Returns the unique sub images, and the duplicates paired with the name of the unique sub image they are identical to
+pub fn split_duplicates( inputs: Vec<InputImage> ) -> ( Vec<InputImage>, Vec<( InputImage, std::path::PathBuf )> ) {
+	let mut unique: Vec<InputImage> = vec!();
+	let mut duplicates: Vec<( InputImage, std::path::PathBuf )> = vec!();
+	let mut unique_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+	for input in inputs {
+		let indices = unique_by_hash.entry( input.trimmed_hash() ).or_default();
+		match indices.iter().find( |index| unique[**index].trimmed_eq( &input ) ) {
+			Some( index ) => {
+				let original = unique[*index].name.to_owned();
+				duplicates.push( ( input, original ) );
+			},
+			None => {
+				indices.push( unique.len() );
+				unique.push( input );
 			}
 		}
-		self.vx = left;
-		self.vw = right - left;
-		self.vy = top;
-		self.vh = bottom - top;
-//		println!( "{:?} {:?} {:?} {:?}", left, right, top, bottom );
+	}
+	( unique, duplicates )
+}
+
+#[cfg(test)]
+mod test_inputimage {
+	fn create_image( name: &str, w: i32, h: i32, data: Vec<u8> ) -> super::InputImage {
+		super::InputImage::new( std::path::Path::new( name ), w, h, data )
+	}
+
+	#[test]
+	fn trimmed_eq_test() {
+		// same 1x1 red pixel, surrounded by differing amounts of transparent space
+		let a = create_image( "a.png", 1, 1, vec![ 255,0,0,255 ] );
+		let mut b = create_image( "b.png", 2, 2, vec![ 0,0,0,0, 0,0,0,0, 0,0,0,0, 255,0,0,255 ] );
+		b.vx = 1;
+		b.vy = 1;
+		b.vw = 1;
+		b.vh = 1;
+		let c = create_image( "c.png", 1, 1, vec![ 0,255,0,255 ] );
+		assert!( a.trimmed_eq( &b ) );
+		assert_eq!( a.trimmed_hash(), b.trimmed_hash() );
+		assert!( !a.trimmed_eq( &c ) );
+		assert!( !b.trimmed_eq( &c ) );
+	}
+
+	#[test]
+	fn split_duplicates_test() {
+		let inputs = vec![
+			create_image( "a.png", 1, 1, vec![ 255,0,0,255 ] ),
+			create_image( "b.png", 1, 1, vec![ 0,255,0,255 ] ),
+			create_image( "c.png", 1, 1, vec![ 255,0,0,255 ] ),
+			create_image( "d.png", 2, 1, vec![ 255,0,0,255, 255,0,0,255 ] )
+		];
+		let ( unique, duplicates ) = super::split_duplicates( inputs );
+		assert_eq!( unique.len(), 3 );
+		assert_eq!( duplicates.len(), 1 );
+		assert_eq!( duplicates[0].0.name, std::path::Path::new( "c.png" ) );
+		assert_eq!( duplicates[0].1, std::path::Path::new( "a.png" ) );
+	}
+
+	#[test]
+	fn trim_threshold_test() {
+		// 4x4 image with a faint halo around an opaque 2x2 centre
+		let mut data = vec![ 0; 4 * 4 * 4 ];
+		for pixel in 0..16 {
+			data[pixel * 4 + 3] = 2;
+		}
+		for ( x, y ) in [ ( 1, 1 ), ( 2, 1 ), ( 1, 2 ), ( 2, 2 ) ] {
+			data[( y * 4 + x ) * 4 + 3] = 255;
+		}
+		let mut image = create_image( "a.png", 4, 4, data.clone() );
+		image.trim( super::TrimMode::Trim, 0, 0 );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 0, 0, 4, 4 ) );
+
+		let mut image = create_image( "a.png", 4, 4, data );
+		image.trim( super::TrimMode::Trim, 2, 0 );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 1, 1, 2, 2 ) );
+	}
+
+	#[test]
+	fn trim_margin_test() {
+		// single opaque pixel in the middle of an 8x8 image
+		let mut data = vec![ 0; 8 * 8 * 4 ];
+		data[( 4 * 8 + 4 ) * 4 + 3] = 255;
+		let mut image = create_image( "a.png", 8, 8, data.clone() );
+		image.trim( super::TrimMode::Trim, 0, 2 );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 2, 2, 5, 5 ) );
+
+		// margin is clamped to the image edges
+		let mut image = create_image( "a.png", 8, 8, data );
+		image.trim( super::TrimMode::Trim, 0, 10 );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 0, 0, 8, 8 ) );
+	}
+
+	#[test]
+	fn trim_edges_test() {
+		// opaque pixels touching the top left corner must not be mistaken for "not found"
+		let mut image = create_image( "a.png", 3, 3, vec![ 0,0,0,255, 0,0,0,0, 0,0,0,0,
+														0,0,0,0, 0,0,0,255, 0,0,0,0,
+														0,0,0,0, 0,0,0,0, 0,0,0,0 ] );
+		assert!( image.trim( super::TrimMode::Trim, 0, 0 ) );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 0, 0, 2, 2 ) );
+
+		// opaque pixels touching the bottom right corner keep the last row and column
+		let mut image = create_image( "a.png", 3, 3, vec![ 0,0,0,0, 0,0,0,0, 0,0,0,0,
+														0,0,0,0, 0,0,0,255, 0,0,0,0,
+														0,0,0,0, 0,0,0,0, 0,0,0,255 ] );
+		assert!( image.trim( super::TrimMode::Trim, 0, 0 ) );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 1, 1, 2, 2 ) );
+		assert!( image.is_trimmed() );
+
+		// fully opaque images are unchanged
+		let mut image = create_image( "a.png", 2, 1, vec![ 0,0,0,255, 0,0,0,255 ] );
+		assert!( image.trim( super::TrimMode::Trim, 0, 0 ) );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 0, 0, 2, 1 ) );
+		assert!( !image.is_trimmed() );
+	}
+
+	#[test]
+	fn trim_empty_test() {
+		let mut image = create_image( "a.png", 2, 2, vec![ 0; 16 ] );
+		assert!( !image.trim( super::TrimMode::Trim, 0, 0 ) );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 0, 0, 1, 1 ) );
+
+		// empty images are still detected when not trimming, but keep their size
+		let mut image = create_image( "a.png", 2, 2, vec![ 0; 16 ] );
+		assert!( !image.trim( super::TrimMode::None, 0, 0 ) );
+		assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), ( 0, 0, 2, 2 ) );
+	}
+
+	#[test]
+	fn trim_mode_test() {
+		let mut data = vec![ 0; 4 * 4 * 4 ];
+		data[( 2 * 4 + 1 ) * 4 + 3] = 255;
+		let expected = [
+			( super::TrimMode::None, ( 0, 0, 4, 4 ), ( 4, 4 ), ( 0, 0 ), false ),
+			( super::TrimMode::Trim, ( 1, 2, 1, 1 ), ( 4, 4 ), ( 1, 2 ), true ),
+			( super::TrimMode::Crop, ( 1, 2, 1, 1 ), ( 1, 1 ), ( 0, 0 ), false ),
+			( super::TrimMode::CropKeepPos, ( 1, 2, 1, 1 ), ( 1, 1 ), ( 1, 2 ), false )
+		];
+		for ( trim_mode, trimmed_rect, source_size, source_offset, is_trimmed ) in expected.iter() {
+			let mut image = create_image( "a.png", 4, 4, data.clone() );
+			assert!( image.trim( *trim_mode, 0, 0 ) );
+			assert_eq!( ( image.vx, image.vy, image.vw, image.vh ), *trimmed_rect, "{:?}", trim_mode );
+			assert_eq!( image.source_size(), *source_size, "{:?}", trim_mode );
+			assert_eq!( image.source_offset(), *source_offset, "{:?}", trim_mode );
+			assert_eq!( image.is_trimmed(), *is_trimmed, "{:?}", trim_mode );
+		}
+	}
+
+	#[test]
+	fn nine_patch_test() {
+		// 5x4 image with a 1 pixel guide border, stretching the middle column and the bottom row of the 3x2 content
+		let b = [ 0, 0, 0, 255 ];
+		let o = [ 0, 0, 0, 0 ];
+		let c = [ 255, 0, 0, 255 ];
+		let pixels = [
+			o, o, b, o, o,
+			o, c, c, c, o,
+			b, c, c, c, o,
+			o, o, o, o, o
+		];
+		let mut image = create_image( "panel.9.png", 5, 4, pixels.concat() );
+		assert!( super::InputImage::is_nine_patch_name( &image.name ) );
+		image.strip_nine_patch_guides();
+		assert_eq!( ( image.w, image.h ), ( 3, 2 ) );
+		assert_eq!( image.data, [ c, c, c, c, c, c ].concat() );
+		assert_eq!( image.nine_slice, Some( super::NineSlice{ left: 1, top: 1, right: 1, bottom: 0 } ) );
+		assert_eq!( image.display_name(), std::path::Path::new( "panel.png" ) );
+		// only the .9 is removed from names with several dots
+		image.name = std::path::PathBuf::from( "ui/button.hover.9.png" );
+		assert_eq!( image.display_name(), std::path::Path::new( "ui/button.hover.png" ) );
+
+		// no guides, so the image is left alone
+		let mut image = create_image( "panel.9.png", 3, 3, [ c; 9 ].concat() );
+		image.strip_nine_patch_guides();
+		assert_eq!( ( image.w, image.h ), ( 3, 3 ) );
+		assert_eq!( image.nine_slice, None );
+		assert_eq!( image.display_name(), std::path::Path::new( "panel.9.png" ) );
+		assert!( !super::InputImage::is_nine_patch_name( std::path::Path::new( "panel.png" ) ) );
 	}
 }
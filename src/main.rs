@@ -1,5 +1,8 @@
 extern crate serde;
 extern crate serde_json;
+extern crate toml;
+extern crate rmp_serde;
+extern crate regex;
 #[macro_use] extern crate serde_derive;
 #[macro_use] extern crate failure;
 #[macro_use] extern crate log;
@@ -10,13 +13,62 @@ mod outputmeta;
 mod outputimage;
 mod shapes;
 mod packer;
+mod maxrects;
+mod skyline;
+mod guillotine;
+mod polygon;
+mod sidecar;
+mod optimiser;
 mod parse_input_filenames;
 mod outputdebug;
 
 use clap::{Arg, Command};
-use std::path::{ PathBuf };
+use std::path::{ Path, PathBuf };
 
 
+// When the atlas spans multiple pages, each page image has its index appended to the output name, e.g. out-0.png, out-1.png
+fn get_page_filename( output_filename: &Path, page_index: usize, page_count: usize ) -> PathBuf {
+	if page_count <= 1 {
+		return output_filename.to_owned();
+	}
+	let stem = output_filename.file_stem().unwrap_or_default().to_string_lossy();
+	let page_name = match output_filename.extension() {
+		Some( ext ) => format!( "{}-{}.{}", stem, page_index, ext.to_string_lossy() ),
+		None => format!( "{}-{}", stem, page_index ),
+	};
+	output_filename.with_file_name( page_name )
+}
+
+// File extension used for the meta output when no --meta-output filename is given
+fn get_meta_extension( meta_template: &str ) -> &str {
+	match meta_template {
+		"libgdx" | "spine" => "atlas",
+		"cocos2d-plist" => "plist",
+		"phaser3" | "pixi" | "dragonbones" => "json",
+		"starling" => "xml",
+		"c-header" => "h",
+		"rust-module" => "rs",
+		"binary" => "bin",
+		_ if meta_template.starts_with("json-") => "json",
+		_ => meta_template
+	}
+}
+
+// Direction the runtimes read by each built-in template expect rotated sub images to be turned in, or None if the meta data
+// format cannot describe rotated sub images, in which case rotation is disabled
+fn get_rotation( meta_template: &str ) -> Option<shapes::Rotation> {
+	match meta_template {
+		"starling" | "cocos2d-plist" | "phaser3" | "pixi" | "dragonbones" => Some( shapes::Rotation::Clockwise ),
+		"css" => None,
+		_ => Some( shapes::Rotation::AntiClockwise )
+	}
+}
+
+// True if the built-in template lists every page itself, rather than describing a single image with meta
+fn template_lists_pages( meta_template: &str ) -> bool {
+	matches!( meta_template, "libgdx" | "phaser3" | "css" | "c-header" | "rust-module" )
+}
+
 fn operate() -> std::result::Result<(), failure::Error> {
 	let matches = Command::new("atlasbuilder")
 		.author("Pete Ward <peteward44@gmail.com>")
@@ -35,7 +87,59 @@ fn operate() -> std::result::Result<(), failure::Error> {
 			.short('t')
 			.long("trim-disable")
 			.action(clap::ArgAction::SetTrue)
-			.help("Disable sub image trimming"))
+			.help("Disable sub image trimming, same as --trim-mode none"))
+		.arg(Arg::new("trim-mode")
+			.long("trim-mode")
+			.action(clap::ArgAction::Set)
+			.value_parser(inputimage::TrimMode::ALL.map(|trim_mode| trim_mode.name()))
+			.default_value("trim")
+			.help("How transparent edges are removed from sub images. none: keep the whole image, trim: remove transparent edges and output their original size and offset in the meta data, crop: remove transparent edges as if the image was never any larger, crop-keep-pos: as crop but keeping the offset in the meta data"))
+		.arg(Arg::new("polygon")
+			.long("polygon")
+			.action(clap::ArgAction::SetTrue)
+			.help("Calculate a convex polygon outline of the visible pixels of each sub image, output in the meta data as vertices, vertices_uv and triangles"))
+		.arg(Arg::new("polygon-max-vertices")
+			.long("polygon-max-vertices")
+			.value_parser(clap::value_parser!(i32).range(4..))
+			.action(clap::ArgAction::Set)
+			.default_value("8")
+			.help("Maximum number of vertices in each polygon outline"))
+		.arg(Arg::new("pivot")
+			.long("pivot")
+			.action(clap::ArgAction::Set)
+			.default_value("0.5,0.5")
+			.help("Default pivot point of each sub image as x,y. Coordinates are normalised to the sub image size, or in pixels if followed by px, e.g. 16px,32px. Can be overridden per sub image in a sidecar file"))
+		.arg(Arg::new("animation-regex")
+			.long("animation-regex")
+			.action(clap::ArgAction::Set)
+			.default_value(outputmeta::DEFAULT_ANIMATION_PATTERN)
+			.help("Regular expression matching the names (without extension) of sub images which are animation frames. The first group is the animation name and the second the frame number, e.g. run_0001 is frame 1 of run"))
+		.arg(Arg::new("animation-fps")
+			.long("animation-fps")
+			.value_parser(clap::value_parser!(f32))
+			.action(clap::ArgAction::Set)
+			.default_value("24")
+			.help("Default frames per second of animations. Can be overridden per animation in the sidecar file of any of its frames"))
+		.arg(Arg::new("skip-empty")
+			.long("skip-empty")
+			.action(clap::ArgAction::SetTrue)
+			.help("Skip completely transparent sub images with a warning, instead of keeping them as a single transparent pixel"))
+		.arg(Arg::new("trim-threshold")
+			.long("trim-threshold")
+			.value_parser(clap::value_parser!(u8))
+			.action(clap::ArgAction::Set)
+			.default_value("0")
+			.help("Pixels with an alpha value less than or equal to this are treated as transparent when trimming"))
+		.arg(Arg::new("trim-margin")
+			.long("trim-margin")
+			.value_parser(clap::value_parser!(i32).range(0..))
+			.action(clap::ArgAction::Set)
+			.default_value("0")
+			.help("Number of transparent pixels to keep around each sub image when trimming"))
+		.arg(Arg::new("duplicates-disable")
+			.long("duplicates-disable")
+			.action(clap::ArgAction::SetTrue)
+			.help("Disable detection of duplicate sub images. By default sub images with identical pixels (after trimming) are only packed once"))
 		.arg(Arg::new("fixed-size")
 			.short('f')
 			.long("fixed-size")
@@ -46,13 +150,19 @@ fn operate() -> std::result::Result<(), failure::Error> {
 			.action(clap::ArgAction::Set)
 			.default_value("4096")
 			.value_parser(clap::value_parser!(i32))
-			.help("Maximum width of output atlas - must be power of 2"))
+			.help("Maximum width of output atlas"))
 		.arg(Arg::new("height")
 			.long("height")
 			.action(clap::ArgAction::Set)
 			.default_value("4096")
 			.value_parser(clap::value_parser!(i32))
-			.help("Maximum height of output atlas - must be power of 2"))
+			.help("Maximum height of output atlas"))
+		.arg(Arg::new("size-constraint")
+			.long("size-constraint")
+			.action(clap::ArgAction::Set)
+			.value_parser(packer::SizeConstraint::ALL.map(|size_constraint| size_constraint.name()))
+			.default_value("pot")
+			.help("Restriction on the output atlas dimensions. pot: powers of 2, any: any size, multiple-of-4: multiples of 4 as required by block compressed formats, square: equal width and height"))
 		.arg(Arg::new("image-output")
 			.short('o')
 			.long("image-output")
@@ -69,14 +179,82 @@ fn operate() -> std::result::Result<(), failure::Error> {
 			.long("meta-template")
 			.action(clap::ArgAction::Set)
 			.default_value("json-hash")
-			.help("Template to use for outputted meta information. Either a name of an existing template (json-hash, json-array, toml, yaml, xml) or a path to a file for your own custom template"))
+			.help("Template to use for outputted meta information. Either a name of an existing template (json-hash, json-array, toml, yaml, xml, libgdx, cocos2d-plist, phaser3, pixi, starling, css, c-header, rust-module), spine or dragonbones for the skeletal animation atlas formats, msgpack or binary for the binary formats, or a path to a file for your own custom template"))
+		.arg(Arg::new("meta-all-pages")
+			.long("meta-all-pages")
+			.action(clap::ArgAction::SetTrue)
+			.help("Output the meta data once for all pages, for a custom --meta-template which lists every page itself. Otherwise custom templates are output once per page, each with only the frames on that page"))
+		.arg(Arg::new("retina")
+			.long("retina")
+			.action(clap::ArgAction::SetTrue)
+			.help("Add a media query to the css template which uses a double resolution version of each atlas image on high density displays, named with @2x, e.g. out@2x.png"))
 		.arg(Arg::new("padding")
 			.short('p')
 			.long("padding")
 			.value_parser(clap::value_parser!(i32))
 			.action(clap::ArgAction::Set)
 			.default_value("2")
-			.help("Pixel padding inbetween subimages"))
+			.help("Pixel padding inbetween subimages and around the edges of the atlas. Overridden by --border-padding and --shape-padding"))
+		.arg(Arg::new("border-padding")
+			.long("border-padding")
+			.value_parser(clap::value_parser!(i32).range(0..))
+			.action(clap::ArgAction::Set)
+			.help("Pixel padding between the edges of the atlas and the subimages. Defaults to --padding"))
+		.arg(Arg::new("shape-padding")
+			.long("shape-padding")
+			.value_parser(clap::value_parser!(i32).range(0..))
+			.action(clap::ArgAction::Set)
+			.help("Pixel padding inbetween neighbouring subimages. Defaults to --padding"))
+		.arg(Arg::new("extrude")
+			.long("extrude")
+			.value_parser(clap::value_parser!(i32).range(0..))
+			.action(clap::ArgAction::Set)
+			.default_value("0")
+			.help("Number of pixels to repeat the edges of each sub image outwards by, to prevent neighbouring pixels bleeding in when filtering"))
+		.arg(Arg::new("algorithm")
+			.long("algorithm")
+			.action(clap::ArgAction::Set)
+			.default_value("maxrects")
+			.value_parser(packer::Algorithm::NAMES)
+			.help("Bin packing algorithm used to place sub images. skyline is much faster than maxrects for large numbers of sub images, but packs less tightly. guillotine matches the layouts of guillotine based runtime texture caches"))
+		.arg(Arg::new("heuristic")
+			.long("heuristic")
+			.action(clap::ArgAction::Set)
+			.default_value("best-short-side-fit")
+			.value_parser(maxrects::Heuristic::ALL.map(|heuristic| heuristic.name()))
+			.help("Rule used to choose where each sub image is placed within the atlas when using the maxrects algorithm"))
+		.arg(Arg::new("skyline-heuristic")
+			.long("skyline-heuristic")
+			.action(clap::ArgAction::Set)
+			.default_value("bottom-left")
+			.value_parser(skyline::Heuristic::ALL.map(|heuristic| heuristic.name()))
+			.help("Rule used to choose where each sub image is placed within the atlas when using the skyline algorithm"))
+		.arg(Arg::new("guillotine-heuristic")
+			.long("guillotine-heuristic")
+			.action(clap::ArgAction::Set)
+			.default_value("best-area-fit")
+			.value_parser(guillotine::Heuristic::ALL.map(|heuristic| heuristic.name()))
+			.help("Rule used to choose where each sub image is placed within the atlas when using the guillotine algorithm"))
+		.arg(Arg::new("guillotine-split")
+			.long("guillotine-split")
+			.action(clap::ArgAction::Set)
+			.default_value("shorter-leftover-axis")
+			.value_parser(guillotine::SplitRule::ALL.map(|split_rule| split_rule.name()))
+			.help("Rule used to choose how the remaining free space is split after placing a sub image when using the guillotine algorithm"))
+		.arg(Arg::new("guillotine-merge")
+			.long("guillotine-merge")
+			.action(clap::ArgAction::SetTrue)
+			.help("Merge neighbouring free rectangles when using the guillotine algorithm"))
+		.arg(Arg::new("sort")
+			.long("sort")
+			.action(clap::ArgAction::Set)
+			.default_value("area")
+			.value_parser(optimiser::SortOrder::ALL.map(|sort_order| sort_order.name()))
+			.help("Order in which sub images are added to the atlas, largest first"))
+		.arg(Arg::new("optimize")
+			.long("optimize")
+			.action(clap::ArgAction::SetTrue)
+			.help("Tries every combination of heuristic and sort order for the chosen algorithm, using whichever produces the smallest atlas. Overrides --heuristic and --sort"))
 		.arg(Arg::new("input")
 			.help("Image filenames to add to atlas")
 			.required(true)
@@ -93,6 +271,19 @@ fn operate() -> std::result::Result<(), failure::Error> {
 			.action(clap::ArgAction::Set)
 			.default_value("")
 			.help("Root directory to use for all relative output paths in the meta data"))
+		.arg(Arg::new("godot-output")
+			.long("godot-output")
+			.action(clap::ArgAction::Set)
+			.help("Directory to write a Godot 4 AtlasTexture resource (.tres) for each sub image into. Resources reference the atlas by a res:// path relative to --output-name-root-dir, which must be set to the Godot project directory. Disables rotation, which AtlasTexture does not support"))
+		.arg(Arg::new("godot-sprite-frames")
+			.long("godot-sprite-frames")
+			.action(clap::ArgAction::SetTrue)
+			.requires("godot-output")
+			.help("Also write a Godot 4 SpriteFrames resource, sprite_frames.tres, to the --godot-output directory, with sub images named with a frame number (walk_0.png, walk_1.png) grouped into animations"))
+		.arg(Arg::new("unity-output")
+			.long("unity-output")
+			.action(clap::ArgAction::SetTrue)
+			.help("Write a Unity TextureImporter .meta file next to each atlas image, e.g. out.png.meta, which slices it into a sprite per sub image with stable sprite IDs. Requires --output-name-root-dir to be set to the Unity project directory. Disables rotation, which Unity sprites do not support"))
 		.get_matches();
 
 	let raw_filenames = matches.get_many::<String>("input").unwrap_or_default().map(|v| v.as_str()).collect::<Vec<_>>();
@@ -100,17 +291,65 @@ fn operate() -> std::result::Result<(), failure::Error> {
 	let output_width = *matches.get_one::<i32>("width").unwrap();
 	let output_height = *matches.get_one::<i32>("height").unwrap();
 	let padding = *matches.get_one::<i32>("padding").unwrap();
+	let border_padding = *matches.get_one::<i32>("border-padding").unwrap_or(&padding);
+	let shape_padding = *matches.get_one::<i32>("shape-padding").unwrap_or(&padding);
+	let size_constraint = packer::SizeConstraint::from_name(matches.get_one::<String>("size-constraint").unwrap()).unwrap();
+	let extrude = *matches.get_one::<i32>("extrude").unwrap();
+	let mut algorithm = match matches.get_one::<String>("algorithm").unwrap().as_str() {
+		"skyline" => packer::Algorithm::Skyline( skyline::Heuristic::from_name(matches.get_one::<String>("skyline-heuristic").unwrap()).unwrap() ),
+		"guillotine" => packer::Algorithm::Guillotine{
+			heuristic: guillotine::Heuristic::from_name(matches.get_one::<String>("guillotine-heuristic").unwrap()).unwrap(),
+			split_rule: guillotine::SplitRule::from_name(matches.get_one::<String>("guillotine-split").unwrap()).unwrap(),
+			merge: matches.get_flag("guillotine-merge"),
+		},
+		_ => packer::Algorithm::MaxRects( maxrects::Heuristic::from_name(matches.get_one::<String>("heuristic").unwrap()).unwrap() ),
+	};
+	let mut sort_order = optimiser::SortOrder::from_name(matches.get_one::<String>("sort").unwrap()).unwrap();
+	let optimize = matches.get_flag("optimize");
 	let meta_template = matches.get_one::<String>("meta-template").unwrap();
+	let rotation = get_rotation(meta_template);
+	let retina = matches.get_flag("retina");
+	let meta_lists_pages = matches.get_flag("meta-all-pages") || template_lists_pages(meta_template);
 	let output_name_root_dir = std::path::Path::new(matches.get_one::<String>("output-name-root-dir").unwrap());
 	let input_name_root_dir = std::path::Path::new(matches.get_one::<String>("input-name-root-dir").unwrap());
 	let output_filename = std::path::Path::new(matches.get_one::<String>("image-output").unwrap());
 	let output_meta_filename = matches.get_one::<String>("meta-output").unwrap();
+	let godot_output = matches.get_one::<String>("godot-output").map(std::path::Path::new);
+	let godot_sprite_frames = matches.get_flag("godot-sprite-frames");
+	if godot_output.is_some() && output_name_root_dir.as_os_str().is_empty() {
+		bail!( "--godot-output requires --output-name-root-dir to be set to the Godot project directory" );
+	}
+	let unity_output = matches.get_flag("unity-output");
+	if unity_output && output_name_root_dir.as_os_str().is_empty() {
+		bail!( "--unity-output requires --output-name-root-dir to be set to the Unity project directory" );
+	}
 	let tilemap_mode = matches.get_flag("tilemap");
-	let allow_trimming = !tilemap_mode && !matches.get_flag("trim-disable");
-	let allow_rotation = !tilemap_mode && !matches.get_flag("rotation-disable");
+	let trim_mode = if tilemap_mode || matches.get_flag("trim-disable") {
+		inputimage::TrimMode::None
+	} else {
+		inputimage::TrimMode::from_name(matches.get_one::<String>("trim-mode").unwrap()).unwrap()
+	};
+	let skip_empty = matches.get_flag("skip-empty");
+	let default_pivot = sidecar::Pivot::parse(matches.get_one::<String>("pivot").unwrap())?;
+	let animation_pattern = regex::Regex::new(matches.get_one::<String>("animation-regex").unwrap())
+		.map_err( |e| format_err!( "Invalid animation regex: {}", e ) )?;
+	if animation_pattern.captures_len() < 3 {
+		bail!( "Animation regex must have two groups, the animation name and the frame number" );
+	}
+	let animation_fps = *matches.get_one::<f32>("animation-fps").unwrap();
+	let mut output_meta = outputmeta::OutputMeta::new( animation_pattern, animation_fps )?;
+	match meta_template.as_str() {
+		"msgpack" | "binary" | "spine" | "dragonbones" => {},
+		_ => output_meta.load_template( meta_template )?
+	}
+	let polygon_outlines = matches.get_flag("polygon");
+	let polygon_max_vertices = *matches.get_one::<i32>("polygon-max-vertices").unwrap() as usize;
+	let trim_threshold = *matches.get_one::<u8>("trim-threshold").unwrap();
+	let trim_margin = *matches.get_one::<i32>("trim-margin").unwrap();
+	let allow_rotation = !tilemap_mode && !matches.get_flag("rotation-disable") && godot_output.is_none() && !unity_output && rotation.is_some();
+	let rotation = rotation.unwrap_or( shapes::Rotation::AntiClockwise );
 	let allow_grow = !matches.get_flag("fixed-size");
-
-	let mut packer = packer::Packer::new( output_width, output_height, allow_grow, allow_rotation, padding );
+	let detect_duplicates = !matches.get_flag("duplicates-disable");
 
 	debug!( "Calculating rects..." );
 	let mut largest_w : i32 = 0;
@@ -118,9 +357,20 @@ fn operate() -> std::result::Result<(), failure::Error> {
 	let mut inputs: Vec<inputimage::InputImage> = vec!();
 	for filename in input_filenames.iter() {
 		let mut input = inputimage::InputImage::load( filename );
-		if allow_trimming
-		{
-			input.trim();
+		let settings = sidecar::SpriteSettings::load( filename )?;
+		let pivot = settings.pivot( default_pivot )?;
+		input.pivot = ( pivot.x.resolve( input.w ), pivot.y.resolve( input.h ) );
+		if let Some( [ left, top, right, bottom ] ) = settings.nine_slice {
+			input.nine_slice = Some( inputimage::NineSlice{ left, top, right, bottom } );
+		}
+		input.fps = settings.fps;
+		input.looping = settings.looping;
+		if !input.trim( trim_mode, trim_threshold, trim_margin ) && skip_empty {
+			eprintln!( "Warning: Skipping empty sub image {:?}", input.name );
+			continue;
+		}
+		if polygon_outlines {
+			input.outline = polygon::outline( &input, trim_threshold, polygon_max_vertices );
 		}
 		if input.w > largest_w {
 			largest_w = input.w;
@@ -132,51 +382,107 @@ fn operate() -> std::result::Result<(), failure::Error> {
 		inputs.push( input );
 	}
 
-	// sort by size then reverse order
-	inputs.sort_by_key( |r| r.vw * r.vh );
-	inputs.reverse();
+	if inputs.is_empty() {
+		bail!( "All input images are empty" );
+	}
+
+	let mut duplicates: Vec<( inputimage::InputImage, PathBuf )> = vec!();
+	if detect_duplicates {
+		( inputs, duplicates ) = inputimage::split_duplicates( inputs );
+		for ( duplicate, original ) in duplicates.iter() {
+			println!( "Sub image {:?} is a duplicate of {:?}", duplicate.name, original );
+		}
+	}
 
+	let create_packer = |algorithm| packer::Packer::new( output_width, output_height, allow_grow, allow_rotation, border_padding, shape_padding, extrude, algorithm, size_constraint );
+	if optimize && !tilemap_mode {
+		let sizes: Vec<shapes::Size> = inputs.iter().map( |input| shapes::Size{ w: input.vw, h: input.vh } ).collect();
+		match optimiser::optimise( &sizes, algorithm, create_packer ) {
+			Some( ( best_algorithm, best_sort_order ) ) => {
+				println!( "Optimised packing using {} heuristic {:?} and sort order {:?}", best_algorithm.name(), best_algorithm.heuristic_name(), best_sort_order.name() );
+				algorithm = best_algorithm;
+				sort_order = best_sort_order;
+			},
+			None => {
+				bail!( "Output size exceeded!" );
+			}
+		}
+	}
+
+	sort_order.sort( &mut inputs, |r| ( r.vw, r.vh ) );
+
+	let mut packer = create_packer( algorithm );
 	for input in inputs.iter() {
 		packer.add( input.vw, input.vh );
 	}
 
 	if tilemap_mode {
 		packer.pack_tilemap(largest_w, largest_h);
-	} else {
-		while !packer.pack() {
-			if !packer.grow() {
-				bail!( "Output size exceeded!" );
-			}
-		}
+	} else if !packer.pack_to_fit() {
+		bail!( "Output size exceeded!" );
 	}
 
-	let mut output_meta = outputmeta::OutputMeta::new();
-	let mut output = outputimage::OutputImage::new( packer.get_w(), packer.get_h() );
+	let mut outputs: Vec<outputimage::OutputImage> = packer.get_pages().iter().map( |page| outputimage::OutputImage::new( page.w, page.h ) ).collect();
 	let pack_results = packer.get_results();
 	for pack_result_index in 0..pack_results.len() {
 		let pack_result: &packer::PackResult = &pack_results[pack_result_index];
 		let input: &inputimage::InputImage = &inputs[pack_result_index];
-		println!( "Copying sub image {:?} x={:?} y={:?} w={:?} h={:?}", input.name, pack_result.rect.x, pack_result.rect.y, pack_result.rect.w, pack_result.rect.h );
-		output.add_input( &input, pack_result.rect.x, pack_result.rect.y, pack_result.rotated );
-		output_meta.add_input( input_name_root_dir,  &input, pack_result.rect.x, pack_result.rect.y, pack_result.rotated );
+		println!( "Copying sub image {:?} page={:?} x={:?} y={:?} w={:?} h={:?}", input.name, pack_result.page, pack_result.rect.x, pack_result.rect.y, pack_result.rect.w, pack_result.rect.h );
+		outputs[pack_result.page].add_input( input, pack_result.rect.x, pack_result.rect.y, pack_result.rotated, rotation, extrude );
+		output_meta.add_input( input_name_root_dir, input, pack_result.rect.x, pack_result.rect.y, pack_result.rotated, rotation, pack_result.page, None );
+	}
+	for ( duplicate, original ) in duplicates.iter() {
+		// duplicates share the packed rect of the sub image they are identical to
+		let original_index = inputs.iter().position( |input| input.name == *original ).unwrap();
+		let pack_result: &packer::PackResult = &pack_results[original_index];
+		output_meta.add_input( input_name_root_dir, duplicate, pack_result.rect.x, pack_result.rect.y, pack_result.rotated, rotation, pack_result.page, Some( original_index ) );
+	}
+	for ( page_index, output ) in outputs.iter().enumerate() {
+		let page_filename = get_page_filename( output_filename, page_index, outputs.len() );
+		println!( "Outputting final image {:?}", page_filename );
+		output.save( &page_filename )?;
+		output_meta.add_page( &page_filename, output.w, output.h );
 	}
-	println!( "Outputting final image {:?}", output_filename );
-	output.save( output_filename )?;
 
-	let output_json_filename: std::path::PathBuf;
-	if output_meta_filename.len() > 0 {
+	let output_json_filename: std::path::PathBuf = if !output_meta_filename.is_empty() {
 		// meta output name was specified on command line, use that
-		output_json_filename = std::path::Path::new(output_meta_filename).to_owned();
+		std::path::Path::new(output_meta_filename).to_owned()
 	} else {
 		// use file extension of template used for default
-		// TODO: detect & parse out hypen to work out file extension
-		if meta_template.starts_with("json-") {
-			output_json_filename = std::path::Path::new("out").with_extension("json");
-		} else {
-			output_json_filename = std::path::Path::new("out").with_extension(meta_template.to_owned());
+		std::path::Path::new("out").with_extension(get_meta_extension(meta_template))
+	};
+	match meta_template.as_str() {
+		"msgpack" => output_meta.save_msgpack( &output_json_filename, output_name_root_dir )?,
+		"binary" => output_meta.save_binary( &output_json_filename )?,
+		"spine" => output_meta.save_spine( &output_json_filename )?,
+		"dragonbones" => {
+			// DragonBones atlases describe a single image, so each page gets its own atlas named like its image
+			for page_index in 0..output_meta.get_page_count() {
+				let page_meta_filename = get_page_filename( &output_json_filename, page_index, output_meta.get_page_count() );
+				println!( "Outputting meta data for page {:?} to {:?}", page_index, page_meta_filename );
+				output_meta.save_dragonbones( &page_meta_filename, page_index )?;
+			}
+		},
+		_ if output_meta.get_page_count() > 1 && !meta_lists_pages => {
+			// the template describes a single page, so each page gets its own meta data named like its image
+			for page_index in 0..output_meta.get_page_count() {
+				let page_meta_filename = get_page_filename( &output_json_filename, page_index, output_meta.get_page_count() );
+				println!( "Outputting meta data for page {:?} to {:?}", page_index, page_meta_filename );
+				output_meta.save( &page_meta_filename, output_name_root_dir, retina, Some( page_index ) )?;
+			}
+		},
+		_ => {
+			output_meta.save( &output_json_filename, output_name_root_dir, retina, None )?;
 		}
 	}
-	output_meta.save( &output_json_filename, meta_template, output_name_root_dir, output_filename, output.w, output.h )?;
+	if let Some( godot_output ) = godot_output {
+		println!( "Outputting Godot resources to {:?}", godot_output );
+		output_meta.save_godot( godot_output, output_name_root_dir, godot_sprite_frames )?;
+	}
+	if unity_output {
+		println!( "Outputting Unity meta data" );
+		output_meta.save_unity( output_name_root_dir )?;
+	}
 	Ok(())
 }
 
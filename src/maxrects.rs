@@ -0,0 +1,257 @@
+use std::cmp;
+use super::shapes;
+use super::packer;
+
+// Rules used to choose which free rectangle a sub image is placed into
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Heuristic {
+	BestShortSideFit,
+	BestLongSideFit,
+	BestAreaFit,
+	BottomLeft,
+	ContactPoint
+}
+
+impl Heuristic {
+	pub const ALL: [Heuristic; 5] = [
+		Heuristic::BestShortSideFit,
+		Heuristic::BestLongSideFit,
+		Heuristic::BestAreaFit,
+		Heuristic::BottomLeft,
+		Heuristic::ContactPoint
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			Heuristic::BestShortSideFit => "best-short-side-fit",
+			Heuristic::BestLongSideFit => "best-long-side-fit",
+			Heuristic::BestAreaFit => "best-area-fit",
+			Heuristic::BottomLeft => "bottom-left",
+			Heuristic::ContactPoint => "contact-point"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<Heuristic> {
+		Heuristic::ALL.iter().copied().find( |heuristic| heuristic.name() == name )
+	}
+}
+
+// True if a is contained within b
+fn rect_contains( a: &shapes::Rect, b: &shapes::Rect ) -> bool {
+	a.x >= b.x && a.y >= b.y 
+		&& a.x+a.w <= b.x+b.w 
+		&& a.y+a.h <= b.y+b.h
+}
+
+fn rect_intersects( a: &shapes::Rect, b: &shapes::Rect ) -> bool {
+	!( a.x >= b.x + b.w
+		|| a.x + a.w <= b.x
+		|| a.y >= b.y + b.h
+		|| a.y + a.h <= b.y )
+}
+
+// Length of the overlap between the ranges [a_start, a_end) and [b_start, b_end)
+fn common_interval_length( a_start: i32, a_end: i32, b_start: i32, b_end: i32 ) -> i32 {
+	if a_end < b_start || b_end < a_start {
+		return 0;
+	}
+	cmp::min( a_end, b_end ) - cmp::max( a_start, b_start )
+}
+
+fn punch_hole_in_rect( parent: &shapes::Rect, hole: &shapes::Rect, new_rects: &mut Vec<shapes::Rect> ) {
+	if hole.x < parent.x + parent.w && hole.x + hole.w > parent.x {
+		// new node at top side of the used node.
+		if hole.y > parent.y && hole.y < parent.y + parent.h {
+			new_rects.push( shapes::Rect{
+				x: parent.x,
+				y: parent.y,
+				w: parent.w,
+				h: hole.y - parent.y
+			} );
+		}
+		
+		// New node at the bottom side of the used node
+		if hole.y + hole.h < parent.y + parent.h {
+			new_rects.push( shapes::Rect{
+				x: parent.x,
+				y: hole.y + hole.h,
+				w: parent.w,
+				h: parent.y + parent.h - hole.y - hole.h
+			} );
+		}
+	}
+
+	if hole.y < parent.y + parent.h && hole.y + hole.h > parent.y {
+		// new node at the left side of the used node.
+		if hole.x > parent.x && hole.x < parent.x + parent.w {
+			new_rects.push( shapes::Rect{
+				x: parent.x,
+				y: parent.y,
+				w: hole.x - parent.x,
+				h: parent.h
+			} );
+		}
+		
+		// new node at the right side of the used node
+		if hole.x + hole.w < parent.x + parent.w {
+			new_rects.push( shapes::Rect{
+				x: hole.x + hole.w,
+				y: parent.y,
+				w: parent.x + parent.w - hole.x - hole.w,
+				h: parent.h
+			} );
+		}
+	}
+}
+
+// MaxRects bin packer. Tracks every maximal free rectangle remaining on the page
+pub struct MaxRectsBin {
+	heuristic: Heuristic,
+	bounds: shapes::Rect,
+	free_rects: Vec<shapes::Rect>,
+	placed_rects: Vec<shapes::Rect>
+}
+
+impl MaxRectsBin {
+	pub fn new( bounds: shapes::Rect, heuristic: Heuristic ) -> MaxRectsBin {
+		MaxRectsBin{
+			heuristic,
+			bounds,
+			free_rects: vec![ bounds ],
+			placed_rects: vec!()
+		}
+	}
+
+	// Total length of the edges of the given rect which touch the page boundary or other placed rects
+	fn contact_point_score( &self, rect: &shapes::Rect ) -> i32 {
+		let mut score = 0;
+		if rect.x == self.bounds.x || rect.x + rect.w == self.bounds.x + self.bounds.w {
+			score += rect.h;
+		}
+		if rect.y == self.bounds.y || rect.y + rect.h == self.bounds.y + self.bounds.h {
+			score += rect.w;
+		}
+		for placed in self.placed_rects.iter() {
+			if placed.x == rect.x + rect.w || placed.x + placed.w == rect.x {
+				score += common_interval_length( placed.y, placed.y + placed.h, rect.y, rect.y + rect.h );
+			}
+			if placed.y == rect.y + rect.h || placed.y + placed.h == rect.y {
+				score += common_interval_length( placed.x, placed.x + placed.w, rect.x, rect.x + rect.w );
+			}
+		}
+		score
+	}
+
+	// Scores placing a w x h rect at the top left of free_rect using the selected heuristic. Lower scores are better
+	fn score_placement( &self, free_rect: &shapes::Rect, w: i32, h: i32 ) -> ( i32, i32 ) {
+		let leftover_horiz = ( free_rect.w - w ).abs();
+		let leftover_vert = ( free_rect.h - h ).abs();
+		let short_side_fit = cmp::min( leftover_horiz, leftover_vert );
+		let long_side_fit = cmp::max( leftover_horiz, leftover_vert );
+		match self.heuristic {
+			Heuristic::BestShortSideFit => ( short_side_fit, long_side_fit ),
+			Heuristic::BestLongSideFit => ( long_side_fit, short_side_fit ),
+			Heuristic::BestAreaFit => ( free_rect.w * free_rect.h - w * h, short_side_fit ),
+			Heuristic::BottomLeft => ( free_rect.y + h, free_rect.x ),
+			// contact score should be maximised, so negate it
+			Heuristic::ContactPoint => ( -self.contact_point_score( &shapes::Rect{ x: free_rect.x, y: free_rect.y, w, h } ), 0 )
+		}
+	}
+
+	fn find_best_free_rect( &self, w: i32, h: i32, allow_rotate: bool ) -> Option<packer::PackResult> {
+		// Find best free rectangle to insert target rect into
+		let mut best_score = ( i32::MAX, i32::MAX );
+		let mut best_rect: shapes::Rect = shapes::Rect{ x:0, y:0, w:0, h: 0 };
+		let mut best_rotated: bool = false;
+		let mut parent_width = 0;
+		let mut parent_height = 0;
+		
+		for rect in self.free_rects.iter() {
+			// Try to place the rectangle in upright (non-flipped) orientation
+			if rect.w >= w && rect.h >= h {
+				let score = self.score_placement( rect, w, h );
+				if score < best_score {
+					best_rect = shapes::Rect{
+						x: rect.x, y: rect.y, w, h
+					};
+					best_score = score;
+					best_rotated = false;
+					parent_width = rect.w;
+					parent_height = rect.h;
+				}
+			}
+
+			// then try fitting it in rotated
+			if allow_rotate && rect.w >= h && rect.h >= w {
+				let score = self.score_placement( rect, h, w );
+				if score < best_score {
+					best_rect = shapes::Rect{
+						x: rect.x, y: rect.y, w: h, h: w
+					};
+					best_score = score;
+					best_rotated = true;
+					parent_width = rect.w;
+					parent_height = rect.h;
+				}
+			}
+		}
+		if best_rect.w == 0 {
+			debug!("No space in atlas");
+			None
+		} else {
+			debug!("Found rect.x={:?} rect.y={:?} rect.w={:?} rect.h={:?}", best_rect.x, best_rect.y, best_rect.w, best_rect.h);
+			debug!("(Parent) rect.w={:?} rect.h={:?}", parent_width, parent_height );
+			
+			Some( packer::PackResult{
+				rect: best_rect, rotated: best_rotated, page: 0
+			} )
+		}
+	}
+
+	fn place_rect( &mut self, rect: &shapes::Rect ) {
+		let mut new_rects: Vec<shapes::Rect> = vec!();
+		self.placed_rects.push( *rect );
+		self.free_rects.retain( |free_rect| {
+			if !rect_intersects( free_rect, rect ) {
+				return true;
+			}
+			punch_hole_in_rect( free_rect, rect, &mut new_rects );
+			false
+		} );
+		
+		for rect in new_rects {
+			self.free_rects.push( rect );
+		}
+		
+		MaxRectsBin::prune_free_rects( &mut self.free_rects );
+		
+//		outputdebug::outputFreeRects( self.w, self.h, free_rects );
+	}
+
+	fn prune_free_rects( free_rects: &mut Vec<shapes::Rect> ) {
+		let mut removed: Vec<usize> = vec!();
+		for i in 0..free_rects.len() {
+			for j in (i+1)..free_rects.len() {
+				if !removed.contains( &i ) && rect_contains( &free_rects[i], &free_rects[j] ) {
+					removed.push( i );
+					break;
+				}
+				if !removed.contains( &j ) && rect_contains( &free_rects[j], &free_rects[i] ) {
+					removed.push( j );
+				}
+			}
+		}
+		removed.sort_unstable();
+		for index in removed.iter().rev() {
+			free_rects.remove( *index );
+		}
+	}
+}
+
+impl packer::BinPacker for MaxRectsBin {
+	fn insert( &mut self, w: i32, h: i32, allow_rotate: bool ) -> Option<packer::PackResult> {
+		let result = self.find_best_free_rect( w, h, allow_rotate )?;
+		self.place_rect( &result.rect );
+		Some( result )
+	}
+}
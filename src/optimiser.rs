@@ -0,0 +1,151 @@
+use std::cmp;
+use super::packer;
+use super::shapes;
+
+// Orders in which sub images are added to the packer. Sub images are always added largest first
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortOrder {
+	Area,
+	MaxSide,
+	Perimeter,
+	Width,
+	Height
+}
+
+impl SortOrder {
+	pub const ALL: [SortOrder; 5] = [
+		SortOrder::Area,
+		SortOrder::MaxSide,
+		SortOrder::Perimeter,
+		SortOrder::Width,
+		SortOrder::Height
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			SortOrder::Area => "area",
+			SortOrder::MaxSide => "max-side",
+			SortOrder::Perimeter => "perimeter",
+			SortOrder::Width => "width",
+			SortOrder::Height => "height"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<SortOrder> {
+		SortOrder::ALL.iter().copied().find( |sort_order| sort_order.name() == name )
+	}
+
+	fn key( &self, w: i32, h: i32 ) -> i32 {
+		match self {
+			SortOrder::Area => w * h,
+			SortOrder::MaxSide => cmp::max( w, h ),
+			SortOrder::Perimeter => 2 * ( w + h ),
+			SortOrder::Width => w,
+			SortOrder::Height => h
+		}
+	}
+
+	// Sorts items largest first, size_of returning the width and height of each item
+	pub fn sort<T, F>( &self, items: &mut [T], size_of: F ) where F: Fn( &T ) -> ( i32, i32 ) {
+		// sort by size then reverse order
+		items.sort_by_key( |item| {
+			let ( w, h ) = size_of( item );
+			self.key( w, h )
+		} );
+		items.reverse();
+	}
+}
+
+// Packs the sub images using every combination of the algorithm's heuristics and sort order, returning the combination which gives
+// the smallest total atlas area. create_packer should return an empty packer configured with the given algorithm.
+// Returns None if the sub images cannot be packed with any combination
+pub fn optimise<F>( sizes: &[shapes::Size], algorithm: packer::Algorithm, create_packer: F ) -> Option<( packer::Algorithm, SortOrder )> where F: Fn( packer::Algorithm ) -> packer::Packer {
+	let mut best: Option<( packer::Algorithm, SortOrder )> = None;
+	let mut best_area = i64::MAX;
+	for sort_order in SortOrder::ALL.iter() {
+		let mut sorted = sizes.to_vec();
+		sort_order.sort( &mut sorted, |size| ( size.w, size.h ) );
+		for variant in algorithm.variants() {
+			let mut packer = create_packer( variant );
+			for size in sorted.iter() {
+				packer.add( size.w, size.h );
+			}
+			if !packer.pack_to_fit() {
+				continue;
+			}
+			let area = packer.get_total_area();
+			debug!( "optimise algorithm={:?} sort_order={:?} area={:?}", variant, sort_order, area );
+			if area < best_area {
+				best_area = area;
+				best = Some( ( variant, *sort_order ) );
+			}
+		}
+	}
+	best
+}
+
+#[cfg(test)]
+mod test_optimiser {
+	use super::packer;
+	use super::shapes;
+	use crate::maxrects;
+	use crate::skyline;
+
+	#[test]
+	fn sort_order_test() {
+		let mut sizes = vec![ ( 10, 10 ), ( 40, 1 ), ( 3, 20 ) ];
+		super::SortOrder::Area.sort( &mut sizes, |size| *size );
+		assert_eq!( sizes, vec![ ( 10, 10 ), ( 3, 20 ), ( 40, 1 ) ] );
+		super::SortOrder::MaxSide.sort( &mut sizes, |size| *size );
+		assert_eq!( sizes, vec![ ( 40, 1 ), ( 3, 20 ), ( 10, 10 ) ] );
+		super::SortOrder::Perimeter.sort( &mut sizes, |size| *size );
+		assert_eq!( sizes, vec![ ( 40, 1 ), ( 3, 20 ), ( 10, 10 ) ] );
+		super::SortOrder::Width.sort( &mut sizes, |size| *size );
+		assert_eq!( sizes, vec![ ( 40, 1 ), ( 10, 10 ), ( 3, 20 ) ] );
+		super::SortOrder::Height.sort( &mut sizes, |size| *size );
+		assert_eq!( sizes, vec![ ( 3, 20 ), ( 10, 10 ), ( 40, 1 ) ] );
+	}
+
+	#[test]
+	fn optimise_test() {
+		let sizes = vec![
+			shapes::Size{ w: 120, h: 8 },
+			shapes::Size{ w: 120, h: 8 },
+			shapes::Size{ w: 8, h: 120 },
+			shapes::Size{ w: 60, h: 60 },
+			shapes::Size{ w: 30, h: 70 }
+		];
+		let algorithm = packer::Algorithm::MaxRects( maxrects::Heuristic::BestShortSideFit );
+		let create_packer = |algorithm| packer::Packer::new( 1024, 1024, true, false, 0, 0, 0, algorithm, packer::SizeConstraint::PowerOfTwo );
+		let ( best_algorithm, best_sort_order ) = super::optimise( &sizes, algorithm, create_packer ).unwrap();
+
+		// the chosen combination must be at least as good as every other combination
+		let mut sorted = sizes.clone();
+		best_sort_order.sort( &mut sorted, |size| ( size.w, size.h ) );
+		let mut best_packer = create_packer( best_algorithm );
+		for size in sorted.iter() {
+			best_packer.add( size.w, size.h );
+		}
+		assert!( best_packer.pack_to_fit() );
+		for sort_order in super::SortOrder::ALL.iter() {
+			let mut sorted = sizes.clone();
+			sort_order.sort( &mut sorted, |size| ( size.w, size.h ) );
+			for variant in algorithm.variants() {
+				let mut packer = create_packer( variant );
+				for size in sorted.iter() {
+					packer.add( size.w, size.h );
+				}
+				assert!( packer.pack_to_fit() );
+				assert!( best_packer.get_total_area() <= packer.get_total_area() );
+			}
+		}
+	}
+
+	#[test]
+	fn optimise_too_large_test() {
+		let sizes = vec![ shapes::Size{ w: 300, h: 10 } ];
+		let algorithm = packer::Algorithm::Skyline( skyline::Heuristic::BottomLeft );
+		let result = super::optimise( &sizes, algorithm, |algorithm| packer::Packer::new( 256, 256, false, false, 0, 0, 0, algorithm, packer::SizeConstraint::PowerOfTwo ) );
+		assert!( result.is_none() );
+	}
+}
@@ -5,16 +5,16 @@ use rand::distributions::{Distribution, Uniform};
 use super::shapes;
 
 #[allow(dead_code)]
-fn write_pixel( data: &mut Vec<u8>, x: i32, y: i32, pitch: i32, r: u8, g: u8, b: u8 ) {
+fn write_pixel( data: &mut [u8], x: i32, y: i32, pitch: i32, r: u8, g: u8, b: u8 ) {
 	let pos = (x + (y * pitch))*4;
-	data[(pos+0) as usize] = r;
+	data[pos as usize] = r;
 	data[(pos+1) as usize] = g;
 	data[(pos+2) as usize] = b;
 	data[(pos+3) as usize] = 255;
 }
 
 #[allow(dead_code)]
-fn draw_rect( data: &mut Vec<u8>, pitch: i32, rect: &shapes::Rect ) {
+fn draw_rect( data: &mut [u8], pitch: i32, rect: &shapes::Rect ) {
     let mut rng = rand::thread_rng();
 	let range = Uniform::from(0..255);
 
@@ -49,13 +49,13 @@ fn draw_rect( data: &mut Vec<u8>, pitch: i32, rect: &shapes::Rect ) {
 }
 
 #[allow(dead_code)]
-pub fn output_free_rects( w: i32, h: i32, free_rects: &mut Vec<shapes::Rect>, index: i32 ) -> std::result::Result<(), failure::Error> {
+pub fn output_free_rects( w: i32, h: i32, free_rects: &mut [shapes::Rect], index: i32 ) -> std::result::Result<(), failure::Error> {
 	let filename = format!( "debug{:?}.png", index );
 	let size = w*h*4;
 	let mut data: Vec<u8> = vec![0; size as usize];
 	for rect in free_rects {
 		println!( "w={:?} h={:?} rect.x={:?} rect.y={:?} rect.w={:?} rect.h={:?}", w, h, rect.x, rect.y, rect.w, rect.h );
-		draw_rect( &mut data, w, &rect,  );
+		draw_rect( &mut data, w, rect );
 	}
 	image::save_buffer( filename, &data, w as u32, h as u32, image::ColorType::Rgba8)?;
 	Ok(())
@@ -3,6 +3,7 @@ extern crate png;
 extern crate oxipng;
 
 use super::inputimage;
+use super::shapes;
 use std::fs::File;
 use std::io::BufWriter;
 //use oxipng::*;
@@ -14,44 +15,80 @@ pub struct OutputImage {
 }
 
 impl OutputImage {
-	pub fn add_input( &mut self, img: &inputimage::InputImage, dx: i32, dy: i32, rotated: bool ) {
+	// Copies the trimmed area of img to dx, dy, turned in the rotation direction if rotated, then duplicates its edge pixels
+	// extrude pixels outwards
+	pub fn add_input( &mut self, img: &inputimage::InputImage, dx: i32, dy: i32, rotated: bool, rotation: shapes::Rotation, extrude: i32 ) {
 		if rotated {
 			for row in 0..img.vh {
 				let src_row = (img.vy+row)*(img.w*4) + img.vx*4;
 				for col in 0..img.vw {
 					let src = src_row + col*4;
-					let dst_row = (dy+(img.vw-col-1))*self.w*4; // transpose col / row
-					let dst = dst_row + (dx+row)*4;
+					// transpose col / row
+					let ( dst_x, dst_y ) = match rotation {
+						shapes::Rotation::AntiClockwise => ( row, img.vw-col-1 ),
+						shapes::Rotation::Clockwise => ( img.vh-row-1, col )
+					};
+					let dst = (dy+dst_y)*self.w*4 + (dx+dst_x)*4;
 					self.data[dst as usize..(dst+4) as usize].copy_from_slice( &img.data[src as usize..(src+4) as usize] );
 				}
 			}
 		} else {
 			for row in 0..img.vh {
 				let srcx = (img.vy+row)*img.w*4 + img.vx*4;
-				let srcy = srcx + img.w*4;
+				let srcy = srcx + img.vw*4;
 				let dstx = ( dy + row )*self.w*4 + dx*4;
-				let dsty = dstx + img.w*4;
+				let dsty = dstx + img.vw*4;
 				self.data[dstx as usize..dsty as usize].copy_from_slice( &img.data[srcx as usize..srcy as usize] );
 			}
 		}
+		if extrude > 0 {
+			let ( w, h ) = if rotated { ( img.vh, img.vw ) } else { ( img.vw, img.vh ) };
+			self.extrude_edges( dx, dy, w, h, extrude );
+		}
+	}
+
+	fn copy_pixel( &mut self, src_x: i32, src_y: i32, dst_x: i32, dst_y: i32 ) {
+		let src = ( ( src_y*self.w + src_x )*4 ) as usize;
+		let dst = ( ( dst_y*self.w + dst_x )*4 ) as usize;
+		self.data.copy_within( src..src+4, dst );
+	}
+
+	// Repeats the edge pixels of the already copied w x h rect at x, y outwards by extrude pixels, including the corners.
+	// Works on the destination pixels so rotation has already been applied
+	fn extrude_edges( &mut self, x: i32, y: i32, w: i32, h: i32, extrude: i32 ) {
+		if w <= 0 || h <= 0 {
+			return;
+		}
+		for row in y..y+h {
+			for offset in 1..=extrude {
+				self.copy_pixel( x, row, x-offset, row );
+				self.copy_pixel( x+w-1, row, x+w-1+offset, row );
+			}
+		}
+		// top and bottom rows include the extruded left and right columns to fill the corners
+		for col in x-extrude..x+w+extrude {
+			for offset in 1..=extrude {
+				self.copy_pixel( col, y, col, y-offset );
+				self.copy_pixel( col, y+h-1, col, y+h-1+offset );
+			}
+		}
 	}
 
 	pub fn new( w: i32, h: i32 ) -> OutputImage {
 		let size = w*h*4;
-		OutputImage { w: w, h: h, data: vec![0; size as usize] }
+		OutputImage { w, h, data: vec![0; size as usize] }
 	}
 	
 	pub fn save( &self, filename: &std::path::Path ) -> std::result::Result<(), failure::Error> {
 //		image::save_buffer( filename, &self.data, self.w as u32, self.h as u32, image::RGBA(8))
 
 		{
-			let parent_dir = filename.parent();
-			if parent_dir.is_some()
+			if let Some(parent_dir) = filename.parent()
 			{
-				std::fs::create_dir_all(parent_dir.unwrap())?;
+				std::fs::create_dir_all(parent_dir)?;
 			}
 			let file = File::create(filename).unwrap();
-			let ref mut w = BufWriter::new(file);
+			let w = &mut BufWriter::new(file);
 
 			let mut encoder = png::Encoder::new(w, self.w as u32, self.h as u32); // Width is 2 pixels and height is 1.
 			encoder.set_color(png::ColorType::Rgba);
@@ -80,11 +117,11 @@ mod test_outputimage {
 							0,0,0,0, 0,0,0,0, 0,0,0,0, 0,0,0,0, 0,0,0,0, 
 							0,0,0,0, 0,0,0,0, 0,0,0,0, 0,0,0,0, 0,0,0,0 ];
 		let mut output_image = super::OutputImage::new( 5, 5 );
-		let input_image = super::inputimage::InputImage{ name: std::path::Path::new("test.png").to_owned(), w: 5, h: 5, vw: 5, vh: 5, vx: 0, vy: 0, data: input_vec.clone() };
-		output_image.add_input( &input_image, 0, 0, false );
-		for x in 0..(5*5*4) {
+		let input_image = super::inputimage::InputImage::new( std::path::Path::new("test.png"), 5, 5, input_vec.clone() );
+		output_image.add_input( &input_image, 0, 0, false, super::shapes::Rotation::AntiClockwise, 0 );
+		for ( x, expected ) in input_vec.iter().enumerate() {
 			let pixel = x/4;
-			assert_eq!( output_image.data[x], input_vec[x], "Test {}x{}", pixel/5, pixel%5 );
+			assert_eq!( output_image.data[x], *expected, "Test {}x{}", pixel/5, pixel%5 );
 		}
 	}
 
@@ -102,13 +139,56 @@ mod test_outputimage {
 							0,0,0,0, 1,1,1,1, 0,0,0,0, 0,0,0,0, 0,0,0,0, 
 							0,0,0,0, 1,1,1,1, 0,0,0,0, 0,0,0,0, 0,0,0,0 ];
 		let mut output_image = super::OutputImage::new( 5, 5 );
-		let input_image = super::inputimage::InputImage{ name: std::path::Path::new("test.png").to_owned(), w: 5, h: 5, vw: 5, vh: 5, vx: 0, vy: 0, data: input_vec };
-		output_image.add_input( &input_image, 0, 0, true );
-		for x in 0..rotated_vec.len() {
+		let input_image = super::inputimage::InputImage::new( std::path::Path::new("test.png"), 5, 5, input_vec );
+		output_image.add_input( &input_image, 0, 0, true, super::shapes::Rotation::AntiClockwise, 0 );
+		for ( x, expected ) in rotated_vec.iter().enumerate() {
 			let pixel = x/4;
-			assert_eq!( output_image.data[x], rotated_vec[x], "Test {}x{}", pixel/5, pixel%5 );
+			assert_eq!( output_image.data[x], *expected, "Test {}x{}", pixel/5, pixel%5 );
+		}
+	}
+
+	#[test]
+	fn add_clockwise_rotated_image() {
+		// 2x3 image turned clockwise into a 3x2 area, so the bottom left pixel ends up top left
+		let input_vec = vec![1,1,1,1, 2,2,2,2,
+							3,3,3,3, 4,4,4,4,
+							5,5,5,5, 6,6,6,6 ];
+		let expected = [ 5, 3, 1,
+						6, 4, 2 ];
+		let mut output_image = super::OutputImage::new( 3, 2 );
+		let input_image = super::inputimage::InputImage::new( std::path::Path::new("test.png"), 2, 3, input_vec );
+		output_image.add_input( &input_image, 0, 0, true, super::shapes::Rotation::Clockwise, 0 );
+		for ( pixel, expected ) in expected.iter().enumerate() {
+			assert_eq!( output_image.data[pixel*4], *expected, "Test {}x{}", pixel%3, pixel/3 );
 		}
 	}
-}
 
+	#[test]
+	fn extrude_image() {
+		// 2x1 trimmed area of a 3x2 image
+		let input_vec = vec![1,1,1,1, 2,2,2,2, 9,9,9,9,
+							9,9,9,9, 9,9,9,9, 9,9,9,9 ];
+		let mut input_image = super::inputimage::InputImage::new( std::path::Path::new("test.png"), 3, 2, input_vec );
+		input_image.vw = 2;
+		input_image.vh = 1;
+		let expected = [ 1, 1, 2, 2,
+						1, 1, 2, 2,
+						1, 1, 2, 2 ];
+		let mut output_image = super::OutputImage::new( 4, 3 );
+		output_image.add_input( &input_image, 1, 1, false, super::shapes::Rotation::AntiClockwise, 1 );
+		for ( pixel, expected ) in expected.iter().enumerate() {
+			assert_eq!( output_image.data[pixel*4], *expected, "Test {}x{}", pixel%4, pixel/4 );
+		}
 
+		// rotated the trimmed area is 1x2, so extruding by 1 fills a 3x4 area
+		let expected = [ 2, 2, 2,
+						2, 2, 2,
+						1, 1, 1,
+						1, 1, 1 ];
+		let mut output_image = super::OutputImage::new( 3, 4 );
+		output_image.add_input( &input_image, 1, 1, true, super::shapes::Rotation::AntiClockwise, 1 );
+		for ( pixel, expected ) in expected.iter().enumerate() {
+			assert_eq!( output_image.data[pixel*4], *expected, "Test {}x{}", pixel%3, pixel/3 );
+		}
+	}
+}
@@ -1,15 +1,25 @@
 use pathdiff::diff_paths;
 use std::path::{Path, PathBuf};
 use std::env;
+use std::fmt::Write;
+use std::sync::LazyLock;
 use tera::Tera;
+use regex::Regex;
 use super::shapes;
 use super::inputimage;
+use super::polygon;
 
 // "https://github.com/urraka/texpack"
 
 #[derive(Serialize)]
 struct JsonHashMeta {
 	pub app: String,
+	// first page basename as an upper snake case identifier, for generated source code
+	pub identifier: String,
+	pub version: String,
+	// pixel format and scale of the atlas images, as used by TexturePacker style formats
+	pub format: String,
+	pub scale: f64,
 	pub path_absolute: String,
 	pub path_relative: String,
 	pub filename: String,
@@ -18,13 +28,16 @@ struct JsonHashMeta {
 	pub size: shapes::Size,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 struct SubImage {
 	pub path_absolute: String,
 	pub path_relative: String,
 	pub filename: String,
 	pub basename: String,
 	pub extension: String,
+	// unique identifiers for generated source code, in upper snake case, e.g. PLAYER_IDLE_0, and camel case, e.g. PlayerIdle0
+	pub identifier: String,
+	pub identifier_camel: String,
 	pub rotated: bool,
 	pub trimmed: bool,
 	pub dest_x: i32,
@@ -34,137 +47,1086 @@ struct SubImage {
 	pub trimmed_w: i32,
 	pub trimmed_h: i32,
 	pub pretrimmed_w: i32,
-	pub pretrimmed_h: i32
+	pub pretrimmed_h: i32,
+	pub page: usize,
+	pub alias_of: Option<String>,
+	// filenames of the sub images which are duplicates of this one
+	pub aliases: Vec<String>,
+	// polygon outline in pretrimmed sub image coordinates, the same vertices in atlas pixel coordinates, and the triangles
+	// making up the polygon as indices into both. Empty unless polygon outlines are enabled
+	pub vertices: Vec<[f32; 2]>,
+	pub vertices_uv: Vec<[f32; 2]>,
+	pub triangles: Vec<[usize; 3]>,
+	// pivot normalised to the pretrimmed size, and in pixels relative to the top left of the frame in the atlas after trimming and rotation
+	pub pivot_x: f64,
+	pub pivot_y: f64,
+	pub pivot_frame_x: f32,
+	pub pivot_frame_y: f32,
+	// borders of nine-slice sub images, relative to the original image
+	pub nine_slice: Option<inputimage::NineSlice>,
+	// offset of the centre of the trimmed area from the centre of the pretrimmed sub image, with y pointing up
+	pub offset_x: f64,
+	pub offset_y: f64,
+	// animation settings from the sidecar file, which are output with the animation instead
+	#[serde(skip)]
+	pub fps: Option<f32>,
+	#[serde(skip)]
+	pub looping: Option<bool>
+}
+
+// Matches sub image names ending in a frame number, e.g. walk_0, walk_1. The first group is the animation name and the second
+// the frame number
+pub const DEFAULT_ANIMATION_PATTERN: &str = "^(.*?[^0-9])[-_. ]?([0-9]+)$";
+
+// Sub images grouped by get_animations
+struct AnimationGroup {
+	name: String,
+	// frame number, if any, and index into the sub images of each frame, in frame order
+	frames: Vec<( Option<u32>, usize )>,
+	fps: f32,
+	looping: bool,
+}
+
+// Sub images named with a frame number, grouped by the name without the number, in frame order
+#[derive(Serialize)]
+struct Animation {
+	pub name: String,
+	pub frames: Vec<String>,
+	pub fps: f32,
+	#[serde(rename = "loop")]
+	pub looping: bool,
+}
+
+// DragonBones texture atlas JSON, describing a single page
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesAtlas {
+	pub name: String,
+	pub image_path: String,
+	pub width: i32,
+	pub height: i32,
+	#[serde(rename = "SubTexture")]
+	pub sub_textures: Vec<DragonBonesSubTexture>,
+}
+
+// Region of a DragonBones texture atlas. The frame is only output for trimmed sub images, and is the pretrimmed size with the
+// position of the frame relative to the trimmed region, so frameX and frameY are zero or negative
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesSubTexture {
+	pub name: String,
+	pub x: i32,
+	pub y: i32,
+	pub width: i32,
+	pub height: i32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub frame_x: Option<i32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub frame_y: Option<i32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub frame_width: Option<i32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub frame_height: Option<i32>,
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub rotated: bool,
+}
+
+// Null terminated strings for the binary meta data, each only stored once
+#[derive(Default)]
+struct StringTable {
+	data: Vec<u8>,
+	offsets: std::collections::HashMap<String, u32>,
+}
+
+impl StringTable {
+	// Offset of the string in the table, adding it if it isn't there yet
+	fn add( &mut self, string: &str ) -> u32 {
+		if let Some( offset ) = self.offsets.get( string ) {
+			return *offset;
+		}
+		let offset = self.data.len() as u32;
+		self.data.extend_from_slice( string.as_bytes() );
+		self.data.push( 0 );
+		self.offsets.insert( string.to_owned(), offset );
+		offset
+	}
+}
+
+#[derive(Serialize)]
+struct Page {
+	pub index: usize,
+	pub path_absolute: String,
+	pub path_relative: String,
+	pub filename: String,
+	pub basename: String,
+	pub extension: String,
+	pub size: shapes::Size,
+}
+
+// Everything output in the meta data, used as the template context and for MessagePack output
+#[derive(Serialize)]
+struct MetaData<'a> {
+	pub meta: JsonHashMeta,
+	pub pages: Vec<Page>,
+	pub frames: Vec<&'a SubImage>,
+	pub animations: std::collections::BTreeMap<String, Animation>,
+	pub retina: bool,
 }
 
 pub struct OutputMeta {
 	subs: Vec<SubImage>,
+	pages: Vec<( PathBuf, shapes::Size )>,
 	tera: Option<Tera>,
+	// name and source of the meta template, set by load_template
+	template: Option<( String, String )>,
+	// splits sub image names into animation names and frame numbers, see DEFAULT_ANIMATION_PATTERN
+	animation_pattern: Regex,
+	// default frames per second of animations
+	animation_fps: f32,
+	// every name generated from the identifiers of the sub images so far, see generated_identifiers, and their camel case
+	// identifiers, so new identifiers can be checked for clashes without going through every sub image
+	taken_identifiers: std::collections::HashSet<String>,
+	taken_identifiers_camel: std::collections::HashSet<String>,
 }
 
 fn get_templates_directory() -> std::result::Result<PathBuf, failure::Error> {
-	match env::current_exe() {
-		Ok(mut exe_path) => {
-			exe_path.pop();
-			exe_path.push("templates");
-			if exe_path.exists() {
-				return Ok(exe_path)
-			}
-		},
-		_ => {},
-	}
-	match env::current_dir() {
-		Ok(mut dir_path) => {
-			dir_path.push("templates");
-			if dir_path.exists() {
-				return Ok(dir_path)
-			}
-		},
-		_ => {},
+	if let Ok(mut exe_path) = env::current_exe() {
+		exe_path.pop();
+		exe_path.push("templates");
+		if exe_path.exists() {
+			return Ok(exe_path)
+		}
+	}
+	if let Ok(mut dir_path) = env::current_dir() {
+		dir_path.push("templates");
+		if dir_path.exists() {
+			return Ok(dir_path)
+		}
 	}
 	bail!("Could not get templates directory");
 }
 
 impl OutputMeta {
-	pub fn new() -> OutputMeta {
-		let templates_directory = get_templates_directory().expect("Could not get templates directory");
-		let tera: Option<Tera>;
+	pub fn new( animation_pattern: Regex, animation_fps: f32 ) -> std::result::Result<OutputMeta, failure::Error> {
+		let templates_directory = get_templates_directory()?;
 		println!( "Using templates directory {:?}", templates_directory );
-		if templates_directory.exists() {
+		let tera: Option<Tera> = if templates_directory.exists() {
 			let dir_string = templates_directory.into_os_string().into_string().unwrap() + "/**/*";
-			tera = Some(Tera::new(dir_string.as_str()).unwrap());
+			let mut tera = Tera::new(dir_string.as_str())
+				.map_err( |e| format_err!( "Failed to load templates: {}", error_messages( &e ).join( ": " ) ) )?;
+			tera.register_filter( "escape_string", escape_string_filter );
+			Some(tera)
 		} else {
-			tera = None;
-		}
-		OutputMeta {
+			None
+		};
+		Ok( OutputMeta {
 			subs: vec!(),
+			pages: vec!(),
 			tera,
-		}
+			template: None,
+			animation_pattern,
+			animation_fps,
+			taken_identifiers: std::collections::HashSet::new(),
+			taken_identifiers_camel: std::collections::HashSet::new(),
+		} )
+	}
+
+	// Resolves template to a template file, or else one of the predefined ones, and checks it compiles and renders sample meta
+	// data, so a broken template is reported before any packing is done
+	pub fn load_template( &mut self, template: &str ) -> std::result::Result<(), failure::Error> {
+		let tera = match self.tera.as_mut() {
+			Some( tera ) => tera,
+			None => bail!("No valid templates configured")
+		};
+		// test if the user has specified a filename, or if template is one of the predefined ones
+		let source = match Path::new(template).try_exists() {
+			Ok(true) => {
+				let source = std::fs::read_to_string(template)?;
+				tera.add_raw_template( template, &source ).map_err( |e| describe_template_error( template, &source, &e ) )?;
+				source
+			},
+			_ => match tera.get_template( template ).ok().and_then( |compiled| compiled.path.clone() ) {
+				Some( path ) => std::fs::read_to_string( path )?,
+				None => bail!( "Template '{}' not found, it is neither a predefined template nor an existing file", template )
+			}
+		};
+		let frames = sample_frames();
+		let context = tera::Context::from_serialize( sample_meta_data( &frames ) )?;
+		tera.render( template, &context ).map_err( |e| describe_template_error( template, &source, &e ) )?;
+		self.template = Some( ( template.to_owned(), source ) );
+		Ok(())
 	}
 
 	fn calculate_absolute_path( &self, image_input_path: &std::path::Path ) -> String {
-		return std::fs::canonicalize(&image_input_path).unwrap().to_str().unwrap().to_owned();
+		std::fs::canonicalize(image_input_path).unwrap().to_str().unwrap().to_owned()
 	}
 
 	fn calculate_relative_path( &self, output_meta_root_dir: &std::path::Path, image_input_path: &std::path::Path ) -> String {
 		if output_meta_root_dir.eq(std::path::Path::new("")) {
 			return image_input_path.to_str().expect("invalid path").to_owned();
 		}
-		return diff_paths(image_input_path, output_meta_root_dir).unwrap().to_str().unwrap().to_owned();
+		diff_paths(image_input_path, output_meta_root_dir).unwrap().to_str().unwrap().to_owned()
 	}
 
 	fn calculate_filename( &self, image_input_path: &std::path::Path ) -> String {
-		return image_input_path.file_name().unwrap().to_str().unwrap().to_owned();
+		image_input_path.file_name().unwrap().to_str().unwrap().to_owned()
 	}
 
 	fn calculate_basename( &self, image_input_path: &std::path::Path ) -> String {
-		return image_input_path.file_stem().unwrap().to_str().unwrap().to_owned();
+		image_input_path.file_stem().unwrap().to_str().unwrap().to_owned()
 	}
 
 	fn calculate_extension( &self, image_input_path: &std::path::Path ) -> String {
-		return image_input_path.extension().unwrap().to_str().unwrap().to_owned();
+		image_input_path.extension().unwrap().to_str().unwrap().to_owned()
+	}
+
+	// Identifiers for a sub image which don't clash with any existing sub image or the names used by the source code templates,
+	// which are then taken. Clashes have a number appended
+	fn unique_identifier( &mut self, basename: &str ) -> ( String, String ) {
+		let identifier = sanitise_identifier( basename );
+		let mut candidate = identifier.clone();
+		let mut suffix = 1;
+		loop {
+			let camel = camel_case_identifier( &candidate );
+			let clashes = RESERVED_IDENTIFIERS.contains( &candidate.as_str() ) || RESERVED_IDENTIFIERS.contains( &camel.as_str() ) ||
+				self.taken_identifiers_camel.contains( &camel ) || generated_identifiers( &candidate ).any( |name| self.taken_identifiers.contains( &name ) );
+			if !clashes {
+				self.taken_identifiers.extend( generated_identifiers( &candidate ) );
+				self.taken_identifiers_camel.insert( camel.clone() );
+				return ( candidate, camel );
+			}
+			suffix += 1;
+			candidate = format!( "{}_{}", identifier, suffix );
+		}
 	}
 
-	pub fn add_input( &mut self, input_name_root_dir: &std::path::Path, img: &inputimage::InputImage, dx: i32, dy: i32, rotated: bool ) {
+	// alias_of is the index of the sub image this one is a duplicate of, if any, in the order they were added
+	#[allow(clippy::too_many_arguments)]
+	pub fn add_input( &mut self, input_name_root_dir: &std::path::Path, img: &inputimage::InputImage, dx: i32, dy: i32, rotated: bool, rotation: shapes::Rotation, page: usize, alias_of: Option<usize> ) {
+		let ( source_w, source_h ) = img.source_size();
+		let ( offset_x, offset_y ) = img.source_offset();
+		let vertices: Vec<[f32; 2]> = img.outline.iter().map( |( x, y )| [ x + offset_x as f32, y + offset_y as f32 ] ).collect();
+		let vertices_uv: Vec<[f32; 2]> = img.outline.iter().map( |( x, y )| {
+			if rotated {
+				// matches the transpose in OutputImage::add_input
+				let ( x, y ) = rotation.transform( *x, *y, img.vw as f32, img.vh as f32 );
+				[ dx as f32 + x, dy as f32 + y ]
+			} else {
+				[ dx as f32 + x, dy as f32 + y ]
+			}
+		} ).collect();
+		let ( origin_x, origin_y ) = img.source_origin();
+		let pivot_trimmed_x = img.pivot.0 - img.vx as f32;
+		let pivot_trimmed_y = img.pivot.1 - img.vy as f32;
+		let ( pivot_frame_x, pivot_frame_y ) = if rotated {
+			rotation.transform( pivot_trimmed_x, pivot_trimmed_y, img.vw as f32, img.vh as f32 )
+		} else {
+			( pivot_trimmed_x, pivot_trimmed_y )
+		};
+		let display_name = img.display_name();
+		let ( identifier, identifier_camel ) = self.unique_identifier( &self.calculate_basename(display_name.as_path()) );
 		let rect = SubImage{
 			path_absolute: self.calculate_absolute_path(img.name.as_path()),
 			path_relative: self.calculate_relative_path(input_name_root_dir, img.name.as_path()),
-			filename: self.calculate_filename(img.name.as_path()),
-			basename: self.calculate_basename(img.name.as_path()),
-			extension: self.calculate_extension(img.name.as_path()),
+			filename: self.calculate_filename(display_name.as_path()),
+			basename: self.calculate_basename(display_name.as_path()),
+			extension: self.calculate_extension(display_name.as_path()),
+			identifier,
+			identifier_camel,
 			rotated,
-			trimmed: true,
+			trimmed: img.is_trimmed(),
 			dest_x: dx,
 			dest_y: dy,
-			trimmed_x: img.vx,
-			trimmed_y: img.vy,
+			trimmed_x: offset_x,
+			trimmed_y: offset_y,
 			trimmed_w: img.vw,
 			trimmed_h: img.vh,
-			pretrimmed_w: img.w,
-			pretrimmed_h: img.h
+			pretrimmed_w: source_w,
+			pretrimmed_h: source_h,
+			page,
+			alias_of: alias_of.map( |original| self.subs[original].filename.clone() ),
+			aliases: vec!(),
+			triangles: polygon::triangulate( vertices.len() ),
+			vertices,
+			vertices_uv,
+			pivot_x: ( img.pivot.0 as f64 - origin_x as f64 ) / source_w as f64,
+			pivot_y: ( img.pivot.1 as f64 - origin_y as f64 ) / source_h as f64,
+			pivot_frame_x,
+			pivot_frame_y,
+			nine_slice: img.nine_slice,
+			offset_x: offset_x as f64 + img.vw as f64 / 2.0 - source_w as f64 / 2.0,
+			offset_y: source_h as f64 / 2.0 - ( offset_y as f64 + img.vh as f64 / 2.0 ),
+			fps: img.fps,
+			looping: img.looping
 		};
+		if let Some( original ) = alias_of {
+			self.subs[original].aliases.push( rect.filename.clone() );
+		}
 		self.subs.push( rect );
 	}
 
-	pub fn save( &self, filename: &std::path::PathBuf, template: &str, output_name_root_dir: &std::path::Path, image_output_path: &std::path::Path, output_width: i32, output_height: i32 ) -> std::result::Result<String, failure::Error> {
-		match self.tera {
-			None => {
-				bail!("No valid templates configured")
-			},
-			_ => {},
-		}
-		let meta = JsonHashMeta {
-			app: "https://github.com/peteward44/atlasbuilder-rust".to_string(),
+	// Pages must be added in page index order, after the page image has been saved
+	pub fn add_page( &mut self, image_output_path: &std::path::Path, output_width: i32, output_height: i32 ) {
+		self.pages.push( ( image_output_path.to_owned(), shapes::Size { w: output_width, h: output_height } ) );
+	}
+
+	fn get_pages( &self, output_name_root_dir: &std::path::Path ) -> Vec<Page> {
+		self.pages.iter().enumerate().map( |( index, ( image_output_path, size ) )| Page {
+			index,
 			path_absolute: self.calculate_absolute_path(image_output_path),
 			path_relative: self.calculate_relative_path(output_name_root_dir, image_output_path),
 			filename: self.calculate_filename(image_output_path),
 			basename: self.calculate_basename(image_output_path),
 			extension: self.calculate_extension(image_output_path),
-			size: shapes::Size {
-				w: output_width,
-				h: output_height
+			size: *size
+		} ).collect()
+	}
+
+	// Everything output in the meta data. retina enables the media query for double resolution atlas images in the css template
+	// If page is set, only that page and the frames and animation frames on it are included
+	fn get_meta_data( &self, output_name_root_dir: &std::path::Path, retina: bool, page: Option<usize> ) -> std::result::Result<MetaData<'_>, failure::Error> {
+		if self.pages.is_empty() {
+			bail!("No pages to output")
+		}
+		let on_page = |sub: &SubImage| page.is_none_or( |page| sub.page == page );
+		let mut pages = self.get_pages( output_name_root_dir );
+		if let Some( page ) = page {
+			pages.retain( |other| other.index == page );
+		}
+		let meta = page_meta( &pages[0] );
+		let animations = self.get_animations()?.into_iter()
+			.filter( |animation| animation.frames.iter().any( |( number, _ )| number.is_some() ) )
+			.map( |animation| Animation {
+				name: animation.name,
+				frames: animation.frames.iter().filter( |( _, index )| on_page( &self.subs[*index] ) ).map( |( _, index )| self.subs[*index].filename.to_owned() ).collect(),
+				fps: animation.fps,
+				looping: animation.looping
+			} )
+			.filter( |animation| !animation.frames.is_empty() )
+			.map( |animation| ( animation.name.to_owned(), animation ) ).collect();
+		Ok( MetaData {
+			meta,
+			pages,
+			frames: self.subs.iter().filter( |sub| on_page( sub ) ).collect(),
+			animations,
+			retina
+		} )
+	}
+
+	pub fn get_page_count( &self ) -> usize {
+		self.pages.len()
+	}
+
+	fn write_file( filename: &std::path::Path, contents: &[u8] ) -> std::result::Result<(), failure::Error> {
+		if let Some(parent_dir) = filename.parent()
+		{
+			std::fs::create_dir_all(parent_dir)?;
+		}
+		std::fs::write(filename, contents)?;
+		Ok(())
+	}
+
+	// Renders the template given to load_template, for a single page if page is set
+	pub fn save( &self, filename: &std::path::Path, output_name_root_dir: &std::path::Path, retina: bool, page: Option<usize> ) -> std::result::Result<String, failure::Error> {
+		let ( template, source ) = match ( &self.tera, &self.template ) {
+			( Some( _ ), Some( template ) ) => template,
+			_ => bail!("No meta template loaded")
+		};
+		let context = tera::Context::from_serialize( self.get_meta_data( output_name_root_dir, retina, page )? )?;
+		let result = self.tera.as_ref().unwrap().render( template, &context ).map_err( |e| describe_template_error( template, source, &e ) )?;
+		OutputMeta::write_file( filename, result.as_bytes() )?;
+		Ok(result)
+	}
+
+	// Writes the same data given to templates as MessagePack, with maps keyed by field name
+	pub fn save_msgpack( &self, filename: &std::path::Path, output_name_root_dir: &std::path::Path ) -> std::result::Result<(), failure::Error> {
+		let meta_data = self.get_meta_data( output_name_root_dir, false, None )?;
+		OutputMeta::write_file( filename, &rmp_serde::to_vec_named( &meta_data )? )
+	}
+
+	// Writes the binary meta data format described in get_binary_meta_data
+	pub fn save_binary( &self, filename: &std::path::Path ) -> std::result::Result<(), failure::Error> {
+		OutputMeta::write_file( filename, &self.get_binary_meta_data()? )
+	}
+
+	// Fixed layout binary meta data, for fast loading. All values are little-endian 32 bit integers, or floats where noted.
+	//
+	// Header, 20 bytes:
+	//   magic "ATLB", version (1), page count, frame count, string table size in bytes
+	// Page records, 12 bytes each:
+	//   filename, width, height
+	// Frame records, 56 bytes each:
+	//   filename, page, flags (1 = rotated, 2 = trimmed), x, y, w, h, trimmed x, trimmed y, pretrimmed w, pretrimmed h,
+	//   pivot x (float), pivot y (float), alias of (filename, or 0xffffffff if the frame isn't a duplicate)
+	// String table:
+	//   null terminated UTF-8 strings. Strings in records are byte offsets into the table
+	fn get_binary_meta_data( &self ) -> std::result::Result<Vec<u8>, failure::Error> {
+		if self.pages.is_empty() {
+			bail!("No pages to output")
+		}
+		let mut strings = StringTable::default();
+		let mut records: Vec<u8> = vec!();
+		let mut write = |value: u32| records.extend_from_slice( &value.to_le_bytes() );
+		for ( image_output_path, size ) in self.pages.iter() {
+			write( strings.add( &self.calculate_filename( image_output_path ) ) );
+			write( size.w as u32 );
+			write( size.h as u32 );
+		}
+		for sub in self.subs.iter() {
+			let flags = if sub.rotated { 1 } else { 0 } | if sub.trimmed { 2 } else { 0 };
+			write( strings.add( &sub.filename ) );
+			write( sub.page as u32 );
+			write( flags );
+			for value in [ sub.dest_x, sub.dest_y, sub.trimmed_w, sub.trimmed_h, sub.trimmed_x, sub.trimmed_y, sub.pretrimmed_w, sub.pretrimmed_h ] {
+				write( value as u32 );
+			}
+			write( ( sub.pivot_x as f32 ).to_bits() );
+			write( ( sub.pivot_y as f32 ).to_bits() );
+			write( sub.alias_of.as_ref().map_or( u32::MAX, |alias_of| strings.add( alias_of ) ) );
+		}
+		let mut data: Vec<u8> = b"ATLB".to_vec();
+		for value in [ 1, self.pages.len() as u32, self.subs.len() as u32, strings.data.len() as u32 ] {
+			data.extend_from_slice( &value.to_le_bytes() );
+		}
+		data.extend_from_slice( &records );
+		data.extend_from_slice( &strings.data );
+		Ok( data )
+	}
+
+	// Writes the Spine 4 texture atlas described in get_spine_atlas
+	pub fn save_spine( &self, filename: &std::path::Path ) -> std::result::Result<(), failure::Error> {
+		OutputMeta::write_file( filename, self.get_spine_atlas()?.as_bytes() )
+	}
+
+	// Spine 4 texture atlas, listing every page followed by its regions. Spine expects rotated regions to be turned
+	// anti-clockwise, marked with rotate: 90. Offsets of trimmed regions are measured from the bottom left of the pretrimmed
+	// image, and nine-slice borders are output as splits
+	fn get_spine_atlas( &self ) -> std::result::Result<String, failure::Error> {
+		if self.pages.is_empty() {
+			bail!("No pages to output")
+		}
+		let mut atlas = String::new();
+		for ( page_index, ( image_output_path, size ) ) in self.pages.iter().enumerate() {
+			writeln!( atlas )?;
+			writeln!( atlas, "{}", self.calculate_filename( image_output_path ) )?;
+			writeln!( atlas, "\tsize: {}, {}", size.w, size.h )?;
+			atlas += "\tfilter: Linear, Linear\n\tpma: false\n\tscale: 1\n";
+			for sub in self.subs.iter().filter( |sub| sub.page == page_index ) {
+				writeln!( atlas, "{}", sub.basename )?;
+				writeln!( atlas, "\tbounds: {}, {}, {}, {}", sub.dest_x, sub.dest_y, sub.trimmed_w, sub.trimmed_h )?;
+				if sub.trimmed {
+					writeln!( atlas, "\toffsets: {}, {}, {}, {}", sub.trimmed_x, sub.pretrimmed_h - sub.trimmed_y - sub.trimmed_h, sub.pretrimmed_w, sub.pretrimmed_h )?;
+				}
+				if sub.rotated {
+					writeln!( atlas, "\trotate: 90" )?;
+				}
+				if let Some( nine_slice ) = sub.nine_slice {
+					writeln!( atlas, "\tsplit: {}, {}, {}, {}", nine_slice.left, nine_slice.right, nine_slice.top, nine_slice.bottom )?;
+				}
 			}
+		}
+		Ok( atlas )
+	}
+
+	// Writes the DragonBones texture atlas for one page, described in get_dragonbones_atlas
+	pub fn save_dragonbones( &self, filename: &std::path::Path, page: usize ) -> std::result::Result<(), failure::Error> {
+		OutputMeta::write_file( filename, serde_json::to_string_pretty( &self.get_dragonbones_atlas( page )? )?.as_bytes() )
+	}
+
+	// DragonBones texture atlas for one page, as DragonBones only supports one image per atlas file. DragonBones expects rotated
+	// regions to be turned clockwise
+	fn get_dragonbones_atlas( &self, page: usize ) -> std::result::Result<DragonBonesAtlas, failure::Error> {
+		let ( image_output_path, size ) = match self.pages.get( page ) {
+			Some( page ) => page,
+			None => bail!("No pages to output")
 		};
-		
-		let mut context = tera::Context::new();
-		context.insert("meta", &meta);
-		context.insert("frames", &self.subs);
+		let sub_textures = self.subs.iter().filter( |sub| sub.page == page ).map( |sub| DragonBonesSubTexture {
+			name: sub.basename.clone(),
+			x: sub.dest_x,
+			y: sub.dest_y,
+			width: sub.trimmed_w,
+			height: sub.trimmed_h,
+			frame_x: sub.trimmed.then_some( -sub.trimmed_x ),
+			frame_y: sub.trimmed.then_some( -sub.trimmed_y ),
+			frame_width: sub.trimmed.then_some( sub.pretrimmed_w ),
+			frame_height: sub.trimmed.then_some( sub.pretrimmed_h ),
+			rotated: sub.rotated
+		} ).collect();
+		Ok( DragonBonesAtlas {
+			name: self.calculate_basename( image_output_path ),
+			image_path: self.calculate_filename( image_output_path ),
+			width: size.w,
+			height: size.h,
+			sub_textures
+		} )
+	}
 
-		// test if template is one of the predefined ones, or if the user has specified a filename
-		let result = match Path::new(template).try_exists() {
-			Ok(true) => {
-				let string = std::fs::read_to_string(template)?;
-				Tera::one_off(string.as_str(), &context, false)?
-			},
-			_ => self.tera.as_ref().unwrap().render(template, &context)?,
+	// res:// path of a file for Godot resources, relative to the output name root dir, which should be the Godot project directory.
+	// Fails if there is no root dir or the file is outside of it, as Godot can't load resources from outside the project
+	fn calculate_godot_path( &self, output_name_root_dir: &std::path::Path, path: &std::path::Path ) -> std::result::Result<String, failure::Error> {
+		Ok( format!( "res://{}", OutputMeta::calculate_project_path( output_name_root_dir, path )? ) )
+	}
+
+	// Path of a file relative to the output name root dir of an engine project, joined with / so it's the same on every platform
+	fn calculate_project_path( output_name_root_dir: &std::path::Path, path: &std::path::Path ) -> std::result::Result<String, failure::Error> {
+		if output_name_root_dir.as_os_str().is_empty() {
+			bail!( "Engine output requires --output-name-root-dir to be set to the project directory" );
+		}
+		let relative_path = match diff_paths( path, output_name_root_dir ) {
+			Some( relative_path ) => relative_path,
+			None => bail!( "Can't make {:?} relative to the project directory {:?}", path, output_name_root_dir )
 		};
-		
-		let parent_dir = filename.parent();
-		if parent_dir.is_some()
-		{
-			std::fs::create_dir_all(parent_dir.unwrap())?;
+		let mut components: Vec<String> = vec!();
+		for component in relative_path.components() {
+			match component {
+				std::path::Component::CurDir => {},
+				std::path::Component::Normal( name ) => components.push( name.to_string_lossy().into_owned() ),
+				_ => bail!( "{:?} is outside of the project directory {:?}", path, output_name_root_dir )
+			}
 		}
-		std::fs::write(filename, result.to_owned())?;
-		Ok(result)
+		Ok( components.join( "/" ) )
+	}
+
+	// Name of a sub image for engine resources, the unique identifier in lower case, so sub images with the same name in
+	// different directories don't overwrite each other
+	fn resource_name( sub: &SubImage ) -> String {
+		sub.identifier.to_lowercase()
+	}
+
+	// Filename of the Godot AtlasTexture resource for a sub image
+	fn godot_resource_filename( sub: &SubImage ) -> String {
+		format!( "{}.tres", OutputMeta::resource_name( sub ) )
+	}
+
+	// Writes a Godot 4 AtlasTexture resource for each sub image into directory, named after its identifier, with the trimmed
+	// edges as the margin. Godot doesn't support rotated regions, so rotation must be disabled when packing. If sprite_frames
+	// is set, also writes sprite_frames.tres, a SpriteFrames resource with the sub images grouped into animations
+	pub fn save_godot( &self, directory: &std::path::Path, output_name_root_dir: &std::path::Path, sprite_frames: bool ) -> std::result::Result<(), failure::Error> {
+		if self.pages.is_empty() {
+			bail!("No pages to output")
+		}
+		std::fs::create_dir_all( directory )?;
+		for sub in self.subs.iter() {
+			if sub.rotated {
+				bail!( "Godot AtlasTexture does not support rotated sub image {:?}", sub.filename );
+			}
+			let resource = format!( "[gd_resource type=\"AtlasTexture\" load_steps=2 format=3]\n\n\
+				[ext_resource type=\"Texture2D\" path=\"{}\" id=\"1\"]\n\n\
+				[resource]\n\
+				atlas = ExtResource(\"1\")\n\
+				region = Rect2({}, {}, {}, {})\n\
+				margin = Rect2({}, {}, {}, {})\n",
+				escape_string( &self.calculate_godot_path( output_name_root_dir, &self.pages[sub.page].0 )? ),
+				sub.dest_x, sub.dest_y, sub.trimmed_w, sub.trimmed_h,
+				sub.trimmed_x, sub.trimmed_y, sub.pretrimmed_w - sub.trimmed_w, sub.pretrimmed_h - sub.trimmed_h );
+			std::fs::write( directory.join( OutputMeta::godot_resource_filename( sub ) ), resource )?;
+		}
+		if sprite_frames {
+			std::fs::write( directory.join( "sprite_frames.tres" ), self.godot_sprite_frames( directory, output_name_root_dir )? )?;
+		}
+		Ok(())
+	}
+
+	// Groups the sub images into animations by their names without any frame number, sorted by name, with frames in frame
+	// order. Sub images without a frame number are animations of one frame. Fails if the frames of an animation set different
+	// fps or loop values in their sidecar files
+	fn get_animations( &self ) -> std::result::Result<Vec<AnimationGroup>, failure::Error> {
+		let mut animations: Vec<AnimationGroup> = vec!();
+		for ( index, sub ) in self.subs.iter().enumerate() {
+			let ( name, number ) = split_frame_number( &self.animation_pattern, &sub.basename );
+			match animations.iter_mut().find( |animation| animation.name == name ) {
+				Some( animation ) => animation.frames.push( ( number, index ) ),
+				None => animations.push( AnimationGroup{ name, frames: vec![ ( number, index ) ], fps: self.animation_fps, looping: true } )
+			}
+		}
+		animations.sort_by( |a, b| a.name.cmp( &b.name ) );
+		for animation in animations.iter_mut() {
+			animation.frames.sort();
+			let frames: Vec<&SubImage> = animation.frames.iter().map( |( _, index )| &self.subs[*index] ).collect();
+			if let Some( fps ) = frames.iter().find_map( |sub| sub.fps ) {
+				if frames.iter().any( |sub| sub.fps.is_some_and( |other| other != fps ) ) {
+					bail!( "Frames of animation {:?} have different fps in their sidecar files", animation.name );
+				}
+				animation.fps = fps;
+			}
+			if let Some( looping ) = frames.iter().find_map( |sub| sub.looping ) {
+				if frames.iter().any( |sub| sub.looping.is_some_and( |other| other != looping ) ) {
+					bail!( "Frames of animation {:?} have different loop settings in their sidecar files", animation.name );
+				}
+				animation.looping = looping;
+			}
+		}
+		Ok( animations )
+	}
+
+	fn godot_sprite_frames( &self, directory: &std::path::Path, output_name_root_dir: &std::path::Path ) -> std::result::Result<String, failure::Error> {
+		let animations = self.get_animations()?;
+		let mut ext_resources = String::new();
+		for ( index, sub ) in self.subs.iter().enumerate() {
+			let path = self.calculate_godot_path( output_name_root_dir, &directory.join( OutputMeta::godot_resource_filename( sub ) ) )?;
+			ext_resources += &format!( "[ext_resource type=\"AtlasTexture\" path=\"{}\" id=\"{}\"]\n", escape_string( &path ), index + 1 );
+		}
+		let animations: Vec<String> = animations.iter().map( |animation| {
+			let frames: Vec<String> = animation.frames.iter().map( |( _, index )| format!( "{{\n\"duration\": 1.0,\n\"texture\": ExtResource(\"{}\")\n}}", index + 1 ) ).collect();
+			format!( "{{\n\"frames\": [{}],\n\"loop\": {},\n\"name\": &\"{}\",\n\"speed\": {:?}\n}}", frames.join( ", " ), animation.looping, escape_string( &animation.name ), animation.fps )
+		} ).collect();
+		Ok( format!( "[gd_resource type=\"SpriteFrames\" load_steps={} format=3]\n\n{}\n[resource]\nanimations = [{}]\n", self.subs.len() + 1, ext_resources, animations.join( ", " ) ) )
+	}
+
+	// Unity asset GUID of a page image, a hash of its path in the project so atlases with the same file name in different
+	// directories don't share a GUID
+	fn unity_guid( output_name_root_dir: &std::path::Path, path: &std::path::Path ) -> std::result::Result<String, failure::Error> {
+		let project_path = OutputMeta::calculate_project_path( output_name_root_dir, path )?;
+		Ok( format!( "{:016x}{:016x}", stable_hash( &project_path, 0 ), stable_hash( &project_path, 1 ) ) )
+	}
+
+	// Writes a Unity TextureImporter .meta file next to each page image, slicing it into a sprite per sub image. Unity rects have
+	// a bottom-left origin, and pivots and borders are relative to the trimmed rect. GUIDs are hashes of the page paths relative to the
+	// output name root dir, which should be the Unity project directory, and sprite IDs are hashes of the unique sprite names, so they
+	// stay the same when the atlas is rebuilt and references to the sprites aren't broken. Unity doesn't support rotated sprites, so
+	// rotation must be disabled when packing
+	pub fn save_unity( &self, output_name_root_dir: &std::path::Path ) -> std::result::Result<(), failure::Error> {
+		if self.pages.is_empty() {
+			bail!("No pages to output")
+		}
+		for ( page_index, ( image_output_path, size ) ) in self.pages.iter().enumerate() {
+			let mut sprites = String::new();
+			let mut name_file_ids = String::new();
+			for sub in self.subs.iter().filter( |sub| sub.page == page_index ) {
+				if sub.rotated {
+					bail!( "Unity does not support rotated sub image {:?}", sub.filename );
+				}
+				let pivot_x = sub.pivot_frame_x as f64 / sub.trimmed_w as f64;
+				let pivot_y = 1.0 - sub.pivot_frame_y as f64 / sub.trimmed_h as f64;
+				// 0 is centre, 9 is custom
+				let alignment = if pivot_x == 0.5 && pivot_y == 0.5 { 0 } else { 9 };
+				// left, bottom, right, top, with the trimmed edges removed
+				let border = match sub.nine_slice {
+					Some( nine_slice ) => [
+						nine_slice.left - sub.trimmed_x,
+						nine_slice.bottom - ( sub.pretrimmed_h - sub.trimmed_y - sub.trimmed_h ),
+						nine_slice.right - ( sub.pretrimmed_w - sub.trimmed_x - sub.trimmed_w ),
+						nine_slice.top - sub.trimmed_y
+					].map( |inset| inset.max( 0 ) ),
+					None => [ 0, 0, 0, 0 ]
+				};
+				let name = OutputMeta::resource_name( sub );
+				let internal_id = ( stable_hash( &name, 0 ) & 0x7fff_ffff_ffff_ffff ) as i64;
+				writeln!( sprites, "    - serializedVersion: 2" )?;
+				writeln!( sprites, "      name: {}", name )?;
+				writeln!( sprites, "      rect:" )?;
+				writeln!( sprites, "        serializedVersion: 2" )?;
+				writeln!( sprites, "        x: {}", sub.dest_x )?;
+				writeln!( sprites, "        y: {}", size.h - sub.dest_y - sub.trimmed_h )?;
+				writeln!( sprites, "        width: {}", sub.trimmed_w )?;
+				writeln!( sprites, "        height: {}", sub.trimmed_h )?;
+				writeln!( sprites, "      alignment: {}", alignment )?;
+				writeln!( sprites, "      pivot: {{x: {}, y: {}}}", pivot_x, pivot_y )?;
+				writeln!( sprites, "      border: {{x: {}, y: {}, z: {}, w: {}}}", border[0], border[1], border[2], border[3] )?;
+				sprites += "      outline: []\n      physicsShape: []\n      tessellationDetail: 0\n      bones: []\n";
+				writeln!( sprites, "      spriteID: {:016x}{:016x}", stable_hash( &name, 1 ), stable_hash( &name, 2 ) )?;
+				writeln!( sprites, "      internalID: {}", internal_id )?;
+				sprites += "      vertices: []\n      indices: \n      edges: []\n      weights: []\n";
+				writeln!( name_file_ids, "      {}: {}", name, internal_id )?;
+			}
+			let guid = OutputMeta::unity_guid( output_name_root_dir, image_output_path )?;
+			let mut meta = String::new();
+			writeln!( meta, "fileFormatVersion: 2" )?;
+			writeln!( meta, "guid: {}", guid )?;
+			meta += "TextureImporter:\n  internalIDToNameTable: []\n  externalObjects: {}\n  serializedVersion: 12\n";
+			meta += "  mipmaps:\n    enableMipMap: 0\n  isReadable: 0\n";
+			meta += "  textureSettings:\n    serializedVersion: 2\n    filterMode: 1\n    wrapU: 1\n    wrapV: 1\n";
+			writeln!( meta, "  maxTextureSize: {}", ( size.w.max( size.h ).max( 32 ) as u32 ).next_power_of_two() )?;
+			meta += "  textureFormat: 1\n  alphaUsage: 1\n  alphaIsTransparency: 1\n  textureType: 8\n  textureShape: 1\n";
+			meta += "  spriteMode: 2\n  spriteExtrude: 1\n  spriteMeshType: 1\n  alignment: 0\n  spritePivot: {x: 0.5, y: 0.5}\n";
+			meta += "  spritePixelsToUnits: 100\n  spriteBorder: {x: 0, y: 0, z: 0, w: 0}\n  spriteGenerateFallbackPhysicsShape: 1\n";
+			meta += "  spriteSheet:\n    serializedVersion: 2\n    sprites:\n";
+			meta += &sprites;
+			meta += "    outline: []\n    physicsShape: []\n    bones: []\n    spriteID: \n    internalID: 0\n";
+			meta += "    vertices: []\n    indices: \n    edges: []\n    weights: []\n    secondaryTextures: []\n";
+			meta += "    nameFileIdTable:\n";
+			meta += &name_file_ids;
+			meta += "  spritePackingTag: \n  pSDRemoveMatte: 0\n  userData: \n  assetBundleName: \n  assetBundleVariant: \n";
+			let mut meta_path = image_output_path.as_os_str().to_owned();
+			meta_path.push( ".meta" );
+			std::fs::write( PathBuf::from( meta_path ), meta )?;
+		}
+		Ok(())
+	}
+}
+
+// Names used alongside the sub image identifiers in the c-header and rust-module templates
+const RESERVED_IDENTIFIERS: [&str; 4] = [ "COUNT", "FRAMES", "PAGES", "Self" ];
+
+// Suffixes the c-header template appends to sub image identifiers for its constants
+const IDENTIFIER_SUFFIXES: [&str; 10] = [ "PAGE", "X", "Y", "W", "H", "TRIMMED_X", "TRIMMED_Y", "SOURCE_W", "SOURCE_H", "ROTATED" ];
+
+// Every name the source code templates generate from a sub image identifier, the identifier itself and the identifier with
+// each of the constant suffixes. Identifiers clash if any of their generated names are the same, e.g. HERO and HERO_TRIMMED
+// both generate HERO_TRIMMED_X
+fn generated_identifiers( identifier: &str ) -> impl Iterator<Item = String> + '_ {
+	std::iter::once( identifier.to_owned() ).chain( IDENTIFIER_SUFFIXES.iter().map( move |suffix| format!( "{}_{}", identifier, suffix ) ) )
+}
+
+// Converts a name to an upper snake case identifier, e.g. "player idle-0" -> PLAYER_IDLE_0. Names starting with a digit are
+// prefixed with FRAME_ so they are valid identifiers
+fn sanitise_identifier( name: &str ) -> String {
+	let mut identifier = String::new();
+	for c in name.chars() {
+		if c.is_ascii_alphanumeric() {
+			identifier.push( c.to_ascii_uppercase() );
+		} else if !identifier.is_empty() && !identifier.ends_with( '_' ) {
+			identifier.push( '_' );
+		}
+	}
+	let identifier = identifier.trim_end_matches( '_' );
+	match identifier.chars().next() {
+		None => "FRAME".to_owned(),
+		Some( c ) if c.is_ascii_digit() => format!( "FRAME_{}", identifier ),
+		_ => identifier.to_owned()
+	}
+}
+
+// Converts an upper snake case identifier to camel case, e.g. PLAYER_IDLE_0 -> PlayerIdle0
+fn camel_case_identifier( identifier: &str ) -> String {
+	identifier.split( '_' ).map( |word| {
+		let mut chars = word.chars();
+		match chars.next() {
+			Some( first ) => first.to_string() + &chars.as_str().to_ascii_lowercase(),
+			None => String::new()
+		}
+	} ).collect()
+}
+
+// 64 bit FNV-1a hash of value, varied by seed. Unlike std's hashers the result is fixed across builds, for IDs in files which
+// must be the same every time the atlas is built
+fn stable_hash( value: &str, seed: u64 ) -> u64 {
+	let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed.wrapping_mul( 0x0000_0100_0000_01b3 );
+	for byte in value.bytes() {
+		hash ^= byte as u64;
+		hash = hash.wrapping_mul( 0x0000_0100_0000_01b3 );
+	}
+	hash
+}
+
+// Meta data describing a page, used as meta for the first page, or for the page being output when each page has its own meta data
+fn page_meta( page: &Page ) -> JsonHashMeta {
+	JsonHashMeta {
+		app: "https://github.com/peteward44/atlasbuilder-rust".to_string(),
+		identifier: sanitise_identifier( &page.basename ),
+		version: env!("CARGO_PKG_VERSION").to_string(),
+		format: "RGBA8888".to_string(),
+		scale: 1.0,
+		path_absolute: page.path_absolute.to_owned(),
+		path_relative: page.path_relative.to_owned(),
+		filename: page.filename.to_owned(),
+		basename: page.basename.to_owned(),
+		extension: page.extension.to_owned(),
+		size: page.size
+	}
+}
+
+// Three frames of an animation for checking templates. The first has every optional value set, the second is a rotated duplicate
+// of the first, and the third is untrimmed with no optional values set, so templates must handle both
+fn sample_frames() -> Vec<SubImage> {
+	let frame = |name: &str| SubImage {
+		path_absolute: format!( "/sample/{}.png", name ),
+		path_relative: format!( "{}.png", name ),
+		filename: format!( "{}.png", name ),
+		basename: name.to_owned(),
+		extension: "png".to_owned(),
+		identifier: sanitise_identifier( name ),
+		identifier_camel: camel_case_identifier( &sanitise_identifier( name ) ),
+		trimmed: true,
+		dest_x: 2,
+		dest_y: 2,
+		trimmed_x: 1,
+		trimmed_y: 1,
+		trimmed_w: 14,
+		trimmed_h: 14,
+		pretrimmed_w: 16,
+		pretrimmed_h: 16,
+		vertices: vec![ [ 1.0, 1.0 ], [ 15.0, 1.0 ], [ 15.0, 15.0 ], [ 1.0, 15.0 ] ],
+		vertices_uv: vec![ [ 2.0, 2.0 ], [ 16.0, 2.0 ], [ 16.0, 16.0 ], [ 2.0, 16.0 ] ],
+		triangles: polygon::triangulate( 4 ),
+		pivot_x: 0.5,
+		pivot_y: 0.5,
+		pivot_frame_x: 7.0,
+		pivot_frame_y: 7.0,
+		nine_slice: Some( inputimage::NineSlice{ left: 4, top: 4, right: 4, bottom: 4 } ),
+		..Default::default()
+	};
+	let mut first = frame( "sample_0" );
+	first.aliases = vec![ "sample_1.png".to_owned() ];
+	let mut second = frame( "sample_1" );
+	second.rotated = true;
+	second.alias_of = Some( first.filename.to_owned() );
+	let mut third = frame( "sample_2" );
+	third.trimmed = false;
+	third.trimmed_x = 0;
+	third.trimmed_y = 0;
+	third.trimmed_w = 16;
+	third.trimmed_h = 16;
+	third.nine_slice = None;
+	vec![ first, second, third ]
+}
+
+// Meta data for checking templates, with a single page containing frames
+fn sample_meta_data( frames: &[SubImage] ) -> MetaData<'_> {
+	let page = Page {
+		index: 0,
+		path_absolute: "/sample/sample.png".to_owned(),
+		path_relative: "sample.png".to_owned(),
+		filename: "sample.png".to_owned(),
+		basename: "sample".to_owned(),
+		extension: "png".to_owned(),
+		size: shapes::Size{ w: 32, h: 32 }
+	};
+	let animation = Animation {
+		name: "sample".to_owned(),
+		frames: frames.iter().map( |frame| frame.filename.to_owned() ).collect(),
+		fps: 24.0,
+		looping: true
+	};
+	MetaData {
+		meta: page_meta( &page ),
+		pages: vec![ page ],
+		frames: frames.iter().collect(),
+		animations: std::collections::BTreeMap::from( [ ( animation.name.to_owned(), animation ) ] ),
+		retina: true
+	}
+}
+
+// Messages of the error and each of its causes
+fn error_messages( error: &tera::Error ) -> Vec<String> {
+	let mut messages = vec![ error.to_string() ];
+	let mut cause = std::error::Error::source( error );
+	while let Some( error ) = cause {
+		messages.push( error.to_string() );
+		cause = error.source();
+	}
+	messages
+}
+
+// Escapes backslashes, quotes and line breaks so value can go in a double quoted string in JSON, C, Rust, CSS or a Godot resource
+fn escape_string( value: &str ) -> String {
+	let mut escaped = String::with_capacity( value.len() );
+	for c in value.chars() {
+		match c {
+			'\\' => escaped += "\\\\",
+			'"' => escaped += "\\\"",
+			'\n' => escaped += "\\n",
+			'\r' => escaped += "\\r",
+			'\t' => escaped += "\\t",
+			_ => escaped.push( c )
+		}
+	}
+	escaped
+}
+
+// Tera filter for escape_string, for names in templates of formats which don't use XML escaping
+fn escape_string_filter( value: &tera::Value, _: &std::collections::HashMap<String, tera::Value> ) -> tera::Result<tera::Value> {
+	match value.as_str() {
+		Some( value ) => Ok( tera::Value::String( escape_string( value ) ) ),
+		None => Err( tera::Error::msg( "Filter `escape_string` was called on a value which isn't a string" ) )
+	}
+}
+
+// Line and column, starting at 1, of the first occurrence of text in source
+fn find_position( source: &str, text: &str ) -> Option<( usize, usize )> {
+	let offset = source.find( text )?;
+	let line_start = source[..offset].rfind( '\n' ).map_or( 0, |index| index + 1 );
+	Some( ( source[..offset].matches( '\n' ).count() + 1, source[line_start..offset].chars().count() + 1 ) )
+}
+
+// Position Tera gives in parse errors, e.g. --> 3:14
+static PARSE_ERROR_POSITION: LazyLock<Regex> = LazyLock::new( || Regex::new( r"--> (\d+):(\d+)" ).unwrap() );
+
+// Variable or filter named in render errors, e.g. Variable `frame.x` not found
+static RENDER_ERROR_NAME: LazyLock<Regex> = LazyLock::new( || Regex::new( "`([^`]+)`" ).unwrap() );
+
+// Error naming the template, and the line and column of the problem where known. Parse errors give their position, render errors
+// only name the missing variable or filter, so the position of its first use in source is given instead, labelled as approximate
+// as the failing use may be a later one
+fn describe_template_error( template: &str, source: &str, error: &tera::Error ) -> failure::Error {
+	let messages = error_messages( error );
+	let detail = messages.last().unwrap();
+	let position = PARSE_ERROR_POSITION.captures( detail )
+		.map( |captures| format!( " line {}, column {}", &captures[1], &captures[2] ) )
+		.or_else( || RENDER_ERROR_NAME.captures( detail )
+			.and_then( |captures| find_position( source, &captures[1] ) )
+			.map( |( line, column )| format!( " line {}, column {} (approximate)", line, column ) ) )
+		.unwrap_or_default();
+	// parse errors come with a snippet of the template, which needs lines of its own
+	let detail = detail.trim_end();
+	let separator = if detail.contains( '\n' ) { ":\n" } else { ": " };
+	format_err!( "Meta template {:?}{}{}{}", template, position, separator, detail )
+}
+
+// Splits a sub image name into an animation name and frame number using the first two groups of pattern, e.g. walk_03 ->
+// ( walk, 3 ). Names which don't match are returned whole
+fn split_frame_number( pattern: &Regex, basename: &str ) -> ( String, Option<u32> ) {
+	let captures = match pattern.captures( basename ) {
+		Some( captures ) => captures,
+		None => return ( basename.to_owned(), None )
+	};
+	match ( captures.get( 1 ), captures.get( 2 ).and_then( |number| number.as_str().parse::<u32>().ok() ) ) {
+		( Some( name ), Some( number ) ) if !name.as_str().is_empty() => ( name.as_str().to_owned(), Some( number ) ),
+		_ => ( basename.to_owned(), None )
+	}
+}
+
+#[cfg(test)]
+mod test_outputmeta {
+	#[test]
+	fn sanitise_identifier_test() {
+		assert_eq!( super::sanitise_identifier( "player idle-0" ), "PLAYER_IDLE_0" );
+		assert_eq!( super::sanitise_identifier( "__hero..run__" ), "HERO_RUN" );
+		assert_eq!( super::sanitise_identifier( "0" ), "FRAME_0" );
+		assert_eq!( super::sanitise_identifier( "ünï" ), "N" );
+		assert_eq!( super::sanitise_identifier( "!!" ), "FRAME" );
+		assert_eq!( super::camel_case_identifier( "PLAYER_IDLE_0" ), "PlayerIdle0" );
+	}
+
+	#[test]
+	fn godot_path_test() {
+		let output_meta = super::OutputMeta::new( super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap(), 24.0 ).unwrap();
+		let root = std::path::Path::new( "/game" );
+		assert_eq!( output_meta.calculate_godot_path( root, std::path::Path::new( "/game/./atlas/out.png" ) ).unwrap(), "res://atlas/out.png" );
+		assert!( output_meta.calculate_godot_path( root, std::path::Path::new( "/other/out.png" ) ).is_err() );
+		assert!( output_meta.calculate_godot_path( std::path::Path::new( "" ), std::path::Path::new( "/game/out.png" ) ).is_err() );
+	}
+
+	#[test]
+	fn escape_string_test() {
+		assert_eq!( super::escape_string( "hero.png" ), "hero.png" );
+		assert_eq!( super::escape_string( "a\"b<c&d\\e\n" ), "a\\\"b<c&d\\\\e\\n" );
+	}
+
+	#[test]
+	fn unity_guid_test() {
+		let root = std::path::Path::new( "/game" );
+		let guid_a = super::OutputMeta::unity_guid( root, std::path::Path::new( "/game/a/out.png" ) ).unwrap();
+		let guid_b = super::OutputMeta::unity_guid( root, std::path::Path::new( "/game/b/out.png" ) ).unwrap();
+		assert_eq!( guid_a.len(), 32 );
+		assert_ne!( guid_a, guid_b );
+		assert_eq!( guid_a, super::OutputMeta::unity_guid( std::path::Path::new( "/game/." ), std::path::Path::new( "/game/./a/out.png" ) ).unwrap() );
+		assert!( super::OutputMeta::unity_guid( std::path::Path::new( "" ), std::path::Path::new( "/game/a/out.png" ) ).is_err() );
+	}
+
+	#[test]
+	fn load_template_test() {
+		let mut output_meta = super::OutputMeta::new( super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap(), 24.0 ).unwrap();
+		let names: Vec<String> = output_meta.tera.as_ref().unwrap().get_template_names().map( |name| name.to_owned() ).collect();
+		assert!( names.len() > 10 );
+		for name in names.iter() {
+			output_meta.load_template( name ).unwrap();
+		}
+		assert!( output_meta.load_template( "does-not-exist" ).is_err() );
+		assert_eq!( super::find_position( "a\n  bc {{ x.y }}", "x.y" ), Some( ( 2, 9 ) ) );
+		assert_eq!( super::find_position( "abc", "d" ), None );
+	}
+
+	#[test]
+	fn binary_meta_data_test() {
+		let sub = super::SubImage{ filename: "a.png".to_owned(), rotated: true, dest_x: 3, pretrimmed_w: 9, pivot_x: 0.5, ..Default::default() };
+		let alias = super::SubImage{ filename: "b.png".to_owned(), alias_of: Some( "a.png".to_owned() ), ..Default::default() };
+		let output_meta = super::OutputMeta{ subs: vec![ sub, alias ], pages: vec![ ( std::path::PathBuf::from( "out.png" ), super::shapes::Size{ w: 4, h: 8 } ) ], tera: None, template: None, animation_pattern: super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap(), animation_fps: 24.0,
+			taken_identifiers: Default::default(), taken_identifiers_camel: Default::default() };
+		let data = output_meta.get_binary_meta_data().unwrap();
+		let read = |offset: usize| u32::from_le_bytes( data[offset..offset + 4].try_into().unwrap() );
+		assert_eq!( &data[0..4], b"ATLB" );
+		assert_eq!( [ read( 4 ), read( 8 ), read( 12 ), read( 16 ) ], [ 1, 1, 2, 20 ] );
+		assert_eq!( data.len(), 20 + 12 + 56 * 2 + 20 );
+		// page
+		assert_eq!( [ read( 20 ), read( 24 ), read( 28 ) ], [ 0, 4, 8 ] );
+		// frames
+		assert_eq!( [ read( 32 ), read( 36 ), read( 40 ), read( 44 ) ], [ 8, 0, 1, 3 ] );
+		assert_eq!( read( 32 + 36 ), 9 );
+		assert_eq!( f32::from_bits( read( 32 + 44 ) ), 0.5 );
+		assert_eq!( read( 32 + 52 ), u32::MAX );
+		assert_eq!( read( 88 + 52 ), 8 );
+		assert_eq!( &data[data.len() - 20..], b"out.png\0a.png\0b.png\0" );
+	}
+
+	#[test]
+	fn dragonbones_atlas_test() {
+		let trimmed = super::SubImage{ basename: "a".to_owned(), trimmed: true, rotated: true, dest_x: 3, dest_y: 4, trimmed_x: 5, trimmed_y: 6, trimmed_w: 7, trimmed_h: 8, pretrimmed_w: 20, pretrimmed_h: 30, ..Default::default() };
+		let untrimmed = super::SubImage{ basename: "b".to_owned(), trimmed_w: 2, trimmed_h: 2, pretrimmed_w: 2, pretrimmed_h: 2, page: 1, ..Default::default() };
+		let pages = vec![ ( std::path::PathBuf::from( "out-0.png" ), super::shapes::Size{ w: 32, h: 32 } ), ( std::path::PathBuf::from( "out-1.png" ), super::shapes::Size{ w: 16, h: 16 } ) ];
+		let output_meta = super::OutputMeta{ subs: vec![ trimmed, untrimmed ], pages, tera: None, template: None, animation_pattern: super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap(), animation_fps: 24.0,
+			taken_identifiers: Default::default(), taken_identifiers_camel: Default::default() };
+		// the frame is positioned relative to the trimmed region, so the offsets are negative
+		let atlas = output_meta.get_dragonbones_atlas( 0 ).unwrap();
+		assert_eq!( ( atlas.name.as_str(), atlas.image_path.as_str(), atlas.width, atlas.height ), ( "out-0", "out-0.png", 32, 32 ) );
+		assert_eq!( atlas.sub_textures, vec![ super::DragonBonesSubTexture{ name: "a".to_owned(), x: 3, y: 4, width: 7, height: 8,
+			frame_x: Some( -5 ), frame_y: Some( -6 ), frame_width: Some( 20 ), frame_height: Some( 30 ), rotated: true } ] );
+		// each page only lists its own sub images, and untrimmed sub images have no frame
+		let atlas = output_meta.get_dragonbones_atlas( 1 ).unwrap();
+		assert_eq!( atlas.image_path, "out-1.png" );
+		assert_eq!( serde_json::to_string( &atlas.sub_textures ).unwrap(), r#"[{"name":"b","x":0,"y":0,"width":2,"height":2}]"# );
+		assert!( output_meta.get_dragonbones_atlas( 2 ).is_err() );
+	}
+
+	#[test]
+	fn spine_atlas_test() {
+		let sub = super::SubImage{ basename: "a".to_owned(), trimmed: true, dest_x: 3, dest_y: 4, trimmed_x: 5, trimmed_y: 6, trimmed_w: 7, trimmed_h: 8, pretrimmed_w: 20, pretrimmed_h: 30,
+			nine_slice: Some( super::inputimage::NineSlice{ left: 1, top: 2, right: 3, bottom: 4 } ), page: 1, ..Default::default() };
+		let pages = vec![ ( std::path::PathBuf::from( "out-0.png" ), super::shapes::Size{ w: 32, h: 32 } ), ( std::path::PathBuf::from( "out-1.png" ), super::shapes::Size{ w: 16, h: 16 } ) ];
+		let output_meta = super::OutputMeta{ subs: vec![ sub ], pages, tera: None, template: None, animation_pattern: super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap(), animation_fps: 24.0,
+			taken_identifiers: Default::default(), taken_identifiers_camel: Default::default() };
+		// offsets are from the bottom left, 30 - 6 - 8 = 16
+		assert_eq!( output_meta.get_spine_atlas().unwrap(), "\nout-0.png\n\tsize: 32, 32\n\tfilter: Linear, Linear\n\tpma: false\n\tscale: 1\n\
+			\nout-1.png\n\tsize: 16, 16\n\tfilter: Linear, Linear\n\tpma: false\n\tscale: 1\n\
+			a\n\tbounds: 3, 4, 7, 8\n\toffsets: 5, 16, 20, 30\n\tsplit: 1, 3, 2, 4\n" );
+	}
+
+	#[test]
+	fn unique_identifier_test() {
+		let mut output_meta = super::OutputMeta{ subs: vec!(), pages: vec!(), tera: None, template: None, animation_pattern: super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap(), animation_fps: 24.0,
+			taken_identifiers: Default::default(), taken_identifiers_camel: Default::default() };
+		for name in [ "walk_0.png", "walk-0.png", "walk0.png", "frames.png", "self.png", "walk_0_x.png", "hero.png", "hero_trimmed.png", "hero_source.png" ] {
+			let img = super::inputimage::InputImage::new( std::path::Path::new( name ), 1, 1, vec![ 0; 4 ] );
+			let ( identifier, identifier_camel ) = output_meta.unique_identifier( &output_meta.calculate_basename( &img.name ) );
+			output_meta.subs.push( super::SubImage{ identifier, identifier_camel, ..Default::default() } );
+		}
+		let identifiers: Vec<( &str, &str )> = output_meta.subs.iter().map( |sub| ( sub.identifier.as_str(), sub.identifier_camel.as_str() ) ).collect();
+		assert_eq!( identifiers, vec![
+			( "WALK_0", "Walk0" ),
+			( "WALK_0_2", "Walk02" ),
+			// WALK0 is unique but Walk0 isn't, then Walk02 is taken
+			( "WALK0_3", "Walk03" ),
+			( "FRAMES_2", "Frames2" ),
+			( "SELF_2", "Self2" ),
+			// clashes with the WALK_0_X constant
+			( "WALK_0_X_2", "Walk0X2" ),
+			( "HERO", "Hero" ),
+			// HERO_TRIMMED_X and HERO_SOURCE_W are constants of HERO
+			( "HERO_TRIMMED_2", "HeroTrimmed2" ),
+			( "HERO_SOURCE_2", "HeroSource2" )
+		] );
+	}
+
+	#[test]
+	fn stable_hash_test() {
+		// FNV-1a reference values
+		assert_eq!( super::stable_hash( "", 0 ), 0xcbf2_9ce4_8422_2325 );
+		assert_eq!( super::stable_hash( "a", 0 ), 0xaf63_dc4c_8601_ec8c );
+		assert_ne!( super::stable_hash( "a", 1 ), super::stable_hash( "a", 0 ) );
+	}
+
+	#[test]
+	fn split_frame_number_test() {
+		let pattern = super::Regex::new( super::DEFAULT_ANIMATION_PATTERN ).unwrap();
+		assert_eq!( super::split_frame_number( &pattern, "walk_03" ), ( "walk".to_owned(), Some( 3 ) ) );
+		assert_eq!( super::split_frame_number( &pattern, "run-10" ), ( "run".to_owned(), Some( 10 ) ) );
+		assert_eq!( super::split_frame_number( &pattern, "jump2" ), ( "jump".to_owned(), Some( 2 ) ) );
+		assert_eq!( super::split_frame_number( &pattern, "idle" ), ( "idle".to_owned(), None ) );
+		assert_eq!( super::split_frame_number( &pattern, "42" ), ( "42".to_owned(), None ) );
+		// frames numbered before the name
+		let pattern = super::Regex::new( "^([0-9]+)_(.*)$" ).unwrap();
+		assert_eq!( super::split_frame_number( &pattern, "02_walk" ), ( "02_walk".to_owned(), None ) );
+		let pattern = super::Regex::new( "^(?:[0-9]+)_(.*)_f([0-9]+)$" ).unwrap();
+		assert_eq!( super::split_frame_number( &pattern, "01_walk_f7" ), ( "walk".to_owned(), Some( 7 ) ) );
 	}
 }
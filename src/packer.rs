@@ -1,223 +1,208 @@
 use std::cmp;
-use std::i32;
 use super::shapes;
+use super::maxrects;
+use super::skyline;
+use super::guillotine;
 
+// Starting width / height of the atlas when it is allowed to grow
+const INITIAL_SIZE: i32 = 128;
+
+#[derive(Clone, Copy)]
 pub struct PackResult {
 	pub rect: shapes::Rect,
-	pub rotated: bool
+	pub rotated: bool,
+	pub page: usize
 }
 
-// True if a is contained within b
-fn rect_contains( a: &shapes::Rect, b: &shapes::Rect ) -> bool {
-	a.x >= b.x && a.y >= b.y 
-		&& a.x+a.w <= b.x+b.w 
-		&& a.y+a.h <= b.y+b.h
+// Algorithm which places rects within a single page
+pub trait BinPacker {
+	// Places a w x h rect, returning where it was placed or None if there is no space left for it
+	fn insert( &mut self, w: i32, h: i32, allow_rotate: bool ) -> Option<PackResult>;
 }
 
-fn rect_intersects( a: &shapes::Rect, b: &shapes::Rect ) -> bool {
-	!( a.x >= b.x + b.w
-		|| a.x + a.w <= b.x
-		|| a.y >= b.y + b.h
-		|| a.y + a.h <= b.y )
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Algorithm {
+	MaxRects( maxrects::Heuristic ),
+	Skyline( skyline::Heuristic ),
+	Guillotine{ heuristic: guillotine::Heuristic, split_rule: guillotine::SplitRule, merge: bool }
 }
 
-fn punch_hole_in_rect( parent: &shapes::Rect, hole: &shapes::Rect, new_rects: &mut Vec<shapes::Rect> ) {
-	if hole.x < parent.x + parent.w && hole.x + hole.w > parent.x {
-		// new node at top side of the used node.
-		if hole.y > parent.y && hole.y < parent.y + parent.h {
-			new_rects.push( shapes::Rect{
-				x: parent.x,
-				y: parent.y,
-				w: parent.w,
-				h: hole.y - parent.y
-			} );
+impl Algorithm {
+	pub const NAMES: [&'static str; 3] = [ "maxrects", "skyline", "guillotine" ];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			Algorithm::MaxRects( _ ) => "maxrects",
+			Algorithm::Skyline( _ ) => "skyline",
+			Algorithm::Guillotine{ .. } => "guillotine"
 		}
-		
-		// New node at the bottom side of the used node
-		if hole.y + hole.h < parent.y + parent.h {
-			new_rects.push( shapes::Rect{
-				x: parent.x,
-				y: hole.y + hole.h,
-				w: parent.w,
-				h: parent.y + parent.h - hole.y - hole.h
-			} );
+	}
+
+	pub fn heuristic_name( &self ) -> String {
+		match self {
+			Algorithm::MaxRects( heuristic ) => heuristic.name().to_string(),
+			Algorithm::Skyline( heuristic ) => heuristic.name().to_string(),
+			Algorithm::Guillotine{ heuristic, split_rule, .. } => format!( "{} {}", heuristic.name(), split_rule.name() )
 		}
 	}
 
-	if hole.y < parent.y + parent.h && hole.y + hole.h > parent.y {
-		// new node at the left side of the used node.
-		if hole.x > parent.x && hole.x < parent.x + parent.w {
-			new_rects.push( shapes::Rect{
-				x: parent.x,
-				y: parent.y,
-				w: hole.x - parent.x,
-				h: parent.h
-			} );
+	// Every heuristic variation of this algorithm
+	pub fn variants( &self ) -> Vec<Algorithm> {
+		match self {
+			Algorithm::MaxRects( _ ) => maxrects::Heuristic::ALL.iter().map( |heuristic| Algorithm::MaxRects( *heuristic ) ).collect(),
+			Algorithm::Skyline( _ ) => skyline::Heuristic::ALL.iter().map( |heuristic| Algorithm::Skyline( *heuristic ) ).collect(),
+			Algorithm::Guillotine{ merge, .. } => guillotine::Heuristic::ALL.iter().flat_map( |heuristic| {
+				guillotine::SplitRule::ALL.iter().map( |split_rule| Algorithm::Guillotine{ heuristic: *heuristic, split_rule: *split_rule, merge: *merge } )
+			} ).collect()
 		}
-		
-		// new node at the right side of the used node
-		if hole.x + hole.w < parent.x + parent.w {
-			new_rects.push( shapes::Rect{
-				x: hole.x + hole.w,
-				y: parent.y,
-				w: parent.x + parent.w - hole.x - hole.w,
-				h: parent.h
-			} );
+	}
+
+	// Creates an empty bin which places rects within bounds
+	pub fn create_bin( &self, bounds: shapes::Rect ) -> Box<dyn BinPacker> {
+		match self {
+			Algorithm::MaxRects( heuristic ) => Box::new( maxrects::MaxRectsBin::new( bounds, *heuristic ) ),
+			Algorithm::Skyline( heuristic ) => Box::new( skyline::SkylineBin::new( bounds, *heuristic ) ),
+			Algorithm::Guillotine{ heuristic, split_rule, merge } => Box::new( guillotine::GuillotineBin::new( bounds, *heuristic, *split_rule, *merge ) )
 		}
 	}
 }
 
+// Restrictions on the dimensions of each atlas page
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SizeConstraint {
+	PowerOfTwo,
+	Any,
+	// required by block compressed texture formats
+	MultipleOf4,
+	// width and height are equal, but otherwise any size
+	Square
+}
+
+impl SizeConstraint {
+	pub const ALL: [SizeConstraint; 4] = [
+		SizeConstraint::PowerOfTwo,
+		SizeConstraint::Any,
+		SizeConstraint::MultipleOf4,
+		SizeConstraint::Square
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			SizeConstraint::PowerOfTwo => "pot",
+			SizeConstraint::Any => "any",
+			SizeConstraint::MultipleOf4 => "multiple-of-4",
+			SizeConstraint::Square => "square"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<SizeConstraint> {
+		SizeConstraint::ALL.iter().copied().find( |size_constraint| size_constraint.name() == name )
+	}
+
+	// Next dimension up from size when growing the atlas. Steps in quarters when not restricted to powers of 2
+	fn grow( &self, size: i32 ) -> i32 {
+		match self {
+			SizeConstraint::PowerOfTwo => size * 2,
+			SizeConstraint::Any | SizeConstraint::Square => size + cmp::max( size / 4, 1 ),
+			SizeConstraint::MultipleOf4 => round_up( size + cmp::max( size / 4, 4 ), 4 )
+		}
+	}
+
+	// Smallest valid dimension which is at least size
+	fn round_up( &self, size: i32 ) -> i32 {
+		match self {
+			SizeConstraint::PowerOfTwo => ( size as u32 ).next_power_of_two() as i32,
+			SizeConstraint::Any | SizeConstraint::Square => size,
+			SizeConstraint::MultipleOf4 => round_up( size, 4 )
+		}
+	}
+}
+
+fn round_up( value: i32, multiple: i32 ) -> i32 {
+	( value + multiple - 1 ) / multiple * multiple
+}
+
 pub struct Packer {
 	w: i32,
 	h: i32,
 	w_limit: i32,
 	h_limit: i32,
+	allow_grow: bool,
 	allow_rotate: bool,
+	algorithm: Algorithm,
+	size_constraint: SizeConstraint,
 	used_rects: Vec<shapes::Rect>,
-	free_rects: Vec<shapes::Rect>,
-	padding: i32,
-	results: Vec<PackResult>
+	// gap between the edges of the page and the sub images
+	border_padding: i32,
+	// gap between neighbouring sub images
+	shape_padding: i32,
+	// extra pixels reserved around each rect for edge extrusion. Results exclude the extruded border
+	extrude: i32,
+	results: Vec<PackResult>,
+	pages: Vec<shapes::Size>
 }
 
 impl Packer {
-	pub fn new( w: i32, h: i32, allow_grow: bool, allow_rotate: bool, padding: i32 ) -> Packer {
-		let w_use = if allow_grow { 128 } else { w };
-		let h_use = if allow_grow { 128 } else { h };
-		let mut free = vec!();
-		free.push( shapes::Rect{ x: padding, y: padding, w: w_use - padding, h: h_use - padding } );
+	#[allow(clippy::too_many_arguments)]
+	pub fn new( w: i32, h: i32, allow_grow: bool, allow_rotate: bool, border_padding: i32, shape_padding: i32, extrude: i32, algorithm: Algorithm, size_constraint: SizeConstraint ) -> Packer {
+		// the maximum size must itself satisfy the constraint
+		let ( w_limit, h_limit ) = match size_constraint {
+			SizeConstraint::MultipleOf4 => ( w - w % 4, h - h % 4 ),
+			SizeConstraint::Square => ( cmp::min( w, h ), cmp::min( w, h ) ),
+			_ => ( w, h )
+		};
+		let w_use = if allow_grow { cmp::min( INITIAL_SIZE, w_limit ) } else { w_limit };
+		let h_use = if allow_grow { cmp::min( INITIAL_SIZE, h_limit ) } else { h_limit };
 		Packer{
 			w: w_use,
 			h: h_use,
-			w_limit: w,
-			h_limit: h,
+			w_limit,
+			h_limit,
 			used_rects: vec!(),
-			free_rects: free,
-			allow_rotate: allow_rotate,
-			padding: padding,
-			results: vec!()
+			allow_grow,
+			allow_rotate,
+			algorithm,
+			size_constraint,
+			border_padding,
+			shape_padding,
+			extrude,
+			results: vec!(),
+			pages: vec!()
 		}
 	}
-	
-//	pub fn get_free_rects( &self ) -> &Vec<shapes::Rect> {
-//		&self.free_rects
-//	}
-
-	fn find_best_free_rect( &self, w: i32, h: i32, free_rects: &Vec<shapes::Rect> ) -> Option<PackResult> {
-		// Find best free rectangle to insert target rect into
-		let mut best_short_side_fit = std::i32::MAX;
-		let mut best_long_side_fit = std::i32::MAX;
-		let mut best_rect: shapes::Rect = shapes::Rect{ x:0, y:0, w:0, h: 0 };
-		let mut best_rotated: bool = false;
-		let mut parent_width = 0;
-		let mut parent_height = 0;
-		
-		for rect in free_rects.iter() {
-			// Try to place the rectangle in upright (non-flipped) orientation
-			if rect.w >= w && rect.h >= h {
-				let leftover_horiz = ( rect.w - w ).abs();
-				let leftover_vert = ( rect.h - h ).abs();
-				let short_side_fit = cmp::min( leftover_horiz, leftover_vert );
-				let long_side_fit = cmp::max( leftover_horiz, leftover_vert );
-				
-				if short_side_fit < best_short_side_fit || ( short_side_fit == best_short_side_fit && long_side_fit < best_long_side_fit ) {
-					best_rect = shapes::Rect{
-						x: rect.x, y: rect.y, w: w, h: h
-					};
-					best_short_side_fit = short_side_fit;
-					best_long_side_fit = long_side_fit;
-					best_rotated = false;
-					parent_width = rect.w;
-					parent_height = rect.h;
-				}
-			}
 
-			// then try fitting it in rotated
-			if self.allow_rotate && rect.w >= h && rect.h >= w {
-				let leftover_horiz = ( rect.w - h ).abs();
-				let leftover_vert = ( rect.h - w ).abs();
-				let short_side_fit = cmp::min( leftover_horiz, leftover_vert );
-				let long_side_fit = cmp::max( leftover_horiz, leftover_vert );
-				
-				if short_side_fit < best_short_side_fit || ( short_side_fit == best_short_side_fit && long_side_fit < best_long_side_fit ) {
-					best_rect = shapes::Rect{
-						x: rect.x, y: rect.y, w: h, h: w
-					};
-					best_short_side_fit = short_side_fit;
-					best_long_side_fit = long_side_fit;
-					best_rotated = true;
-				}
-			}
-		}
-		if best_rect.w == 0 {
-			debug!("No space in atlas");
-			None
-		} else {
-			debug!("Found rect.x={:?} rect.y={:?} rect.w={:?} rect.h={:?}", best_rect.x, best_rect.y, best_rect.w, best_rect.h);
-			debug!("(Parent) rect.w={:?} rect.h={:?}", parent_width, parent_height );
-			
-			Some( PackResult{
-				rect: best_rect, rotated: best_rotated
-			} )
-		}
+	fn attempt_pack( &self, w: i32, h: i32, bin: &mut dyn BinPacker ) -> Option<PackResult> {
+		debug!( "attempt_pack w={:?} h={:?} self.shape_padding={:?} self.extrude={:?}", w, h, self.shape_padding, self.extrude );
+		// every rect reserves the shape padding on its right and bottom edges
+		let border = self.shape_padding + self.extrude * 2;
+		let mut result = bin.insert( w + border, h + border, self.allow_rotate )?;
+		debug!( "result.rect.x = {:?} result.rect.w = {:?} result.rect.y = {:?} result.rect.h = {:?}", result.rect.x, result.rect.w, result.rect.y, result.rect.h );
+		result.rect.x += self.extrude;
+		result.rect.y += self.extrude;
+		result.rect.w -= border;
+		result.rect.h -= border;
+		Some( result )
 	}
 
-	fn attempt_pack( &self, w: i32, h: i32, free_rects: &mut Vec<shapes::Rect> ) -> Option<PackResult> {
-		debug!( "attempt_pack w={:?} h={:?} self.padding={:?}", w, h, self.padding );
-		let result_option = self.find_best_free_rect( w + self.padding, h + self.padding, free_rects );
-		match result_option {
-			Some( mut result ) => {
-				let mut new_rects: Vec<shapes::Rect> = vec!();
-				free_rects.retain( |free_rect| {
-					if !rect_intersects( free_rect, &result.rect ) {
-						return true;
-					}
-					punch_hole_in_rect( free_rect, &result.rect, &mut new_rects );
-					return false;
-				} );
-				
-				for rect in new_rects {
-					free_rects.push( rect );
-				}
-				
-				self.prune_free_rects( free_rects );
-				
-//				outputdebug::outputFreeRects( self.w, self.h, free_rects );
-				
-				debug!( "result.rect.x = {:?} result.rect.w = {:?} result.rect.y = {:?} result.rect.h = {:?}", result.rect.x, result.rect.w, result.rect.y, result.rect.h );
-				result.rect.w -= self.padding;
-				result.rect.h -= self.padding;
-				Some( result )
-			}
-			None => {
-				None
-			}
-		}
-	}
-	
-	fn prune_free_rects( &self, free_rects: &mut Vec<shapes::Rect> ) {
-		let mut removed: Vec<usize> = vec!();
-		for i in 0..free_rects.len() {
-			for j in (i+1)..free_rects.len() {
-				if !removed.contains( &i ) && rect_contains( &free_rects[i], &free_rects[j] ) {
-					removed.push( i );
-					break;
-				}
-				if !removed.contains( &j ) && rect_contains( &free_rects[j], &free_rects[i] ) {
-					removed.push( j );
-				}
-			}
-		}
-		removed.sort_unstable();
-		for index in removed.iter().rev() {
-			free_rects.remove( *index );
-		}
+	fn create_bin( &self, w: i32, h: i32 ) -> Box<dyn BinPacker> {
+		// the shape padding reserved on the right / bottom of the last rects can overlap the border padding
+		let bounds = shapes::Rect{
+			x: self.border_padding,
+			y: self.border_padding,
+			w: w - self.border_padding * 2 + self.shape_padding,
+			h: h - self.border_padding * 2 + self.shape_padding
+		};
+		self.algorithm.create_bin( bounds )
 	}
-	
+
 	pub fn add( &mut self, w: i32, h: i32 ) {
-		self.used_rects.push( shapes::Rect{ x: 0, y: 0, w: w, h: h } );
+		self.used_rects.push( shapes::Rect{ x: 0, y: 0, w, h } );
 	}
 	
 	pub fn pack_tilemap(&mut self, tile_w: i32, tile_h: i32) {
+		// leave room for the extruded border, sub images remain centred within each tile
+		let tile_w = tile_w + self.extrude * 2;
+		let tile_h = tile_h + self.extrude * 2;
 		let mut row_count: i32;
 		let column_count: i32;
 		{
@@ -226,7 +211,7 @@ impl Packer {
 			let tile_aspect_ratio: f32 = tile_h as f32 / tile_w as f32;
 			column_count = (subimage_count_sqrt * tile_aspect_ratio).ceil() as i32;
 			row_count = (float_subimage_count / column_count as f32) as i32;
-			if self.used_rects.len() % column_count as usize > 0 {
+			if !self.used_rects.len().is_multiple_of( column_count as usize ) {
 				row_count += 1;
 			}
 			self.w = column_count * tile_w;
@@ -234,9 +219,8 @@ impl Packer {
 			
 			debug!("self.w={:?} self.h={:?} float_subimage_count={:?} tile_aspect_ratio={:?} row_count={:?} column_count={:?}", self.w, self.h, float_subimage_count, tile_aspect_ratio, row_count, column_count);
 		}
-		let mut tile_index = 0;
 		let mut new_results: Vec<PackResult> = vec!();
-		for used_rect in self.used_rects.iter() {
+		for ( tile_index, used_rect ) in ( 0_i32.. ).zip( self.used_rects.iter() ) {
 			let tile_x = tile_index % column_count;
 			let tile_y = tile_index / column_count;
 			let x = tile_w * tile_x + ((tile_w - used_rect.w) / 2);
@@ -247,20 +231,20 @@ impl Packer {
 					x, y, w: tile_w, h: tile_h,
 				},
 				rotated: false,
+				page: 0,
 			};
 			new_results.push(packed_rect);
-			tile_index += 1;
 		}
 		self.results = new_results;
+		self.pages = vec![ shapes::Size{ w: self.w, h: self.h } ];
 	}
 	
 	pub fn pack( &mut self ) -> bool {
 		let mut new_results: Vec<PackResult> = vec!();
-		let mut free_rects: Vec<shapes::Rect> = vec!();
-		free_rects.push( shapes::Rect{ x: self.padding, y: self.padding, w: self.w - self.padding, h: self.h - self.padding } );
+		let mut bin = self.create_bin( self.w, self.h );
 		new_results.reserve( self.results.len() );
 		for used_rect in self.used_rects.iter() {
-			let result = self.attempt_pack( used_rect.w, used_rect.h, &mut free_rects );
+			let result = self.attempt_pack( used_rect.w, used_rect.h, bin.as_mut() );
 			let cont = match result {
 				None => {
 					false
@@ -271,38 +255,145 @@ impl Packer {
 					true
 				},
 			};
-			if cont == false {
+			if !cont {
 				return false;
 			}
 		}
 		self.results = new_results;
-		self.free_rects = free_rects;
+		self.pages = vec![ shapes::Size{ w: self.w, h: self.h } ];
 		true
 	}
 
+	// Packs as many of the given sub images as possible onto a single page of size w x h, storing their results.
+	// Returns the indices of the sub images which did not fit
+	fn pack_page( &self, indices: &[usize], w: i32, h: i32, page: usize, results: &mut [Option<PackResult>] ) -> Vec<usize> {
+		let mut bin = self.create_bin( w, h );
+		let mut leftover: Vec<usize> = vec!();
+		for index in indices.iter() {
+			let used_rect = &self.used_rects[*index];
+			match self.attempt_pack( used_rect.w, used_rect.h, bin.as_mut() ) {
+				Some( mut result ) => {
+					result.page = page;
+					results[*index] = Some( result );
+				},
+				None => {
+					leftover.push( *index );
+				}
+			}
+		}
+		leftover
+	}
+
+	// Used when the sub images will not fit onto a single page of the maximum size.
+	// Fills pages of the maximum size in turn, spilling whatever does not fit onto the next page, then shrinks the last page
+	pub fn pack_multipage( &mut self ) -> bool {
+		let mut results: Vec<Option<PackResult>> = vec![ None; self.used_rects.len() ];
+		let mut pages: Vec<shapes::Size> = vec!();
+		let mut remaining: Vec<usize> = ( 0..self.used_rects.len() ).collect();
+		let mut last_page: Vec<usize> = vec!();
+		while !remaining.is_empty() {
+			let leftover = self.pack_page( &remaining, self.w_limit, self.h_limit, pages.len(), &mut results );
+			if leftover.len() == remaining.len() {
+				debug!( "Sub image too large to fit on an empty page" );
+				return false;
+			}
+			pages.push( shapes::Size{ w: self.w_limit, h: self.h_limit } );
+			last_page = remaining.into_iter().filter( |index| !leftover.contains( index ) ).collect();
+			remaining = leftover;
+		}
+
+		if self.allow_grow && !pages.is_empty() {
+			// repack the last page at the smallest size it will fit in
+			let last_page_index = pages.len() - 1;
+			let mut size = ( cmp::min( INITIAL_SIZE, self.w_limit ), cmp::min( INITIAL_SIZE, self.h_limit ) );
+			loop {
+				if self.pack_page( &last_page, size.0, size.1, last_page_index, &mut results ).is_empty() {
+					pages[last_page_index] = shapes::Size{ w: size.0, h: size.1 };
+					break;
+				}
+				match self.next_size( size.0, size.1 ) {
+					Some( next ) => size = next,
+					None => break
+				}
+			}
+		}
+
+		self.results = results.into_iter().flatten().collect();
+		if let Some( first ) = pages.first() {
+			self.w = first.w;
+			self.h = first.h;
+		}
+		self.pages = pages;
+		true
+	}
+
+	// Packs all sub images, growing the atlas and spilling onto additional pages as required.
+	// Returns false if a sub image is too large to fit onto a page
+	pub fn pack_to_fit( &mut self ) -> bool {
+		let packed = self.pack() || self.grow() || {
+			// too large for a single page, spill the remainder onto additional pages
+			self.pack_multipage()
+		};
+		if packed && self.allow_grow {
+			self.shrink_pages();
+		}
+		packed
+	}
+
+	// Crops any unused space from the right and bottom of each page, keeping to the size constraint
+	fn shrink_pages( &mut self ) {
+		for ( page_index, page ) in self.pages.iter_mut().enumerate() {
+			let mut used_w = 0;
+			let mut used_h = 0;
+			for result in self.results.iter().filter( |result| result.page == page_index ) {
+				used_w = cmp::max( used_w, result.rect.x + result.rect.w + self.extrude + self.border_padding );
+				used_h = cmp::max( used_h, result.rect.y + result.rect.h + self.extrude + self.border_padding );
+			}
+			if self.size_constraint == SizeConstraint::Square {
+				used_w = cmp::max( used_w, used_h );
+				used_h = used_w;
+			}
+			page.w = cmp::min( page.w, self.size_constraint.round_up( cmp::max( used_w, 1 ) ) );
+			page.h = cmp::min( page.h, self.size_constraint.round_up( cmp::max( used_h, 1 ) ) );
+		}
+		if let Some( first ) = self.pages.first() {
+			self.w = first.w;
+			self.h = first.h;
+		}
+	}
+
+	// Calculates the next size up from w x h when growing the atlas, or None if already at the maximum size
+	fn next_size( &self, w: i32, h: i32 ) -> Option<( i32, i32 )> {
+		if w >= self.w_limit && h >= self.h_limit {
+			return None;
+		}
+		let mut w = w;
+		let mut h = h;
+		if self.size_constraint == SizeConstraint::Square {
+			w = self.size_constraint.grow( cmp::max( w, h ) );
+			h = w;
+		} else if w >= self.w_limit {
+			h = self.size_constraint.grow( h );
+		} else if h >= self.h_limit || w < h {
+			w = self.size_constraint.grow( w );
+		} else {
+			h = self.size_constraint.grow( h );
+		}
+		Some( ( cmp::min( w, self.w_limit ), cmp::min( h, self.h_limit ) ) )
+	}
+
 	// Expensive operation
 	pub fn grow( &mut self ) -> bool {
 		loop {
-			if self.w >= self.w_limit && self.h >= self.h_limit {
-				return false;
-			}
-			if self.w >= self.w_limit {
-				self.h *= 2;
-//				println!( "self.h={:?}", self.h );
-			} else if self.h >= self.h_limit {
-				self.w *= 2;
-//				println!( "self.w={:?}", self.w );
-			} else {
-				if self.w < self.h {
-					self.w *= 2;
-//					println!( "self.w={:?}", self.w );
-				} else {
-					self.h *= 2;
-//					println!( "self.h={:?}", self.h );
+			match self.next_size( self.w, self.h ) {
+				Some( ( w, h ) ) => {
+					self.w = w;
+					self.h = h;
+				},
+				None => {
+					return false;
 				}
 			}
-			self.w = std::cmp::min( self.w, self.w_limit );
-			self.h = std::cmp::min( self.h, self.h_limit );
 			if self.pack() {
 				break;
 			}
@@ -314,13 +405,14 @@ impl Packer {
 	pub fn get_results( &self ) -> &Vec<PackResult> {
 		&self.results
 	}
-	
-	pub fn get_w( &self ) -> i32 {
-		self.w
+
+	pub fn get_pages( &self ) -> &Vec<shapes::Size> {
+		&self.pages
 	}
-	
-	pub fn get_h( &self ) -> i32 {
-		self.h
+
+	// Combined area of all pages
+	pub fn get_total_area( &self ) -> i64 {
+		self.pages.iter().map( |page| page.w as i64 * page.h as i64 ).sum()
 	}
 }
 
@@ -338,48 +430,48 @@ mod test_packer {
 
 	#[test]
 	fn basic_packer_test() {
-		let mut packer = super::Packer::new( 256, 256, false, false, 0 );
+		let mut packer = super::Packer::new( 256, 256, false, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::PowerOfTwo );
 
 		packer.add( 10, 10 );
 		let result1 = packer.pack();
-		assert_eq!( result1, true );
+		assert!( result1 );
 		assert_pack_result( &packer.get_results()[0], 0, 0, 10, 10, false, "Test 1" );
 
 		packer.add( 10, 10 );
 		let result2 = packer.pack();
-		assert_eq!( result2, true );
+		assert!( result2 );
 		assert_pack_result( &packer.get_results()[1], 0, 10, 10, 10, false, "Test 2" );
 
 		packer.add( 50, 10 );
 		let result3 = packer.pack();
-		assert_eq!( result3, true );
+		assert!( result3 );
 		assert_pack_result( &packer.get_results()[2], 10, 0, 50, 10, false, "Test 3" );
 			
 		packer.add( 23, 75 );
 		let result5 = packer.pack();
-		assert_eq!( result5, true );
+		assert!( result5 );
 		assert_pack_result( &packer.get_results()[3], 0, 20, 23, 75, false, "Test 4" );
 
 		packer.add( 50, 50 );
 		let result4 = packer.pack();
-		assert_eq!( result4, true );
+		assert!( result4 );
 		assert_pack_result( &packer.get_results()[4], 0, 95, 50, 50, false, "Test 5" );
 	}
 	
 	#[test]
 	fn automatic_grow_test() {
-		let mut packer = super::Packer::new( 1024, 1024, true, false, 0 );
+		let mut packer = super::Packer::new( 1024, 1024, true, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::PowerOfTwo );
 		assert_eq!( packer.w, 128 );
 		assert_eq!( packer.h, 128 );
 		packer.add( 200, 100 );
 		let result1 = packer.pack();
-		assert_eq!( result1, false );
+		assert!( !result1 );
 		let grow_result = packer.grow();
-		assert_eq!( grow_result, true );
+		assert!( grow_result );
 		assert_eq!( packer.w, 256 );
 		assert_eq!( packer.h, 256 );
 		let result2 = packer.pack();
-		assert_eq!( result2, true );
+		assert!( result2 );
 		assert_pack_result( &packer.get_results()[0], 0, 0, 200, 100, false, "Test 1" );
 	}
 	
@@ -407,18 +499,169 @@ mod test_packer {
 			super::shapes::Rect{ x: 0, y: 0, w: 273, h: 367 },
 			super::shapes::Rect{ x: 0, y: 0, w: 302, h: 396 }
 		];
-		let mut packer = super::Packer::new( 4096, 4096, false, true, 0 );
+		let mut packer = super::Packer::new( 4096, 4096, false, true, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::PowerOfTwo );
 		for rect in rects {
 			packer.add( rect.w, rect.h );
 		}
 		let pack_result = packer.pack();
-		assert_eq!( pack_result, true );
+		assert!( pack_result );
 		let results = packer.get_results();
 		for result in results {
 			debug!( "x={:?} y={:?} w={:?} h={:?}", result.rect.x, result.rect.y, result.rect.w, result.rect.h );
 		}
 		//assert_eq!( false, true );
 	}
-}
 
+	#[test]
+	fn multipage_test() {
+		let mut packer = super::Packer::new( 256, 256, true, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 200, 200 );
+		packer.add( 200, 200 );
+		packer.add( 50, 50 );
+		assert!( !packer.pack() );
+		assert!( !packer.grow() );
+		assert!( packer.pack_multipage() );
+		let pages = packer.get_pages();
+		assert_eq!( pages.len(), 2 );
+		assert_eq!( pages[0].w, 256 );
+		assert_eq!( pages[0].h, 256 );
+		assert_eq!( pages[1].w, 256 );
+		assert_eq!( pages[1].h, 256 );
+		let results = packer.get_results();
+		assert_eq!( results.len(), 3 );
+		assert_pack_result( &results[0], 0, 0, 200, 200, false, "Page 0" );
+		assert_eq!( results[0].page, 0 );
+		assert_pack_result( &results[1], 0, 0, 200, 200, false, "Page 1" );
+		assert_eq!( results[1].page, 1 );
+		assert_pack_result( &results[2], 0, 200, 50, 50, false, "Page 0 leftover space" );
+		assert_eq!( results[2].page, 0 );
+	}
+
+	#[test]
+	fn multipage_shrink_last_page_test() {
+		let mut packer = super::Packer::new( 256, 256, true, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 200, 200 );
+		packer.add( 100, 100 );
+		packer.add( 100, 100 );
+		assert!( packer.pack_multipage() );
+		let pages = packer.get_pages();
+		assert_eq!( pages.len(), 2 );
+		// last page is shrunk to fit its contents
+		assert_eq!( pages[1].w, 128 );
+		assert_eq!( pages[1].h, 256 );
+		assert_eq!( packer.get_results()[2].page, 1 );
+	}
 
+	#[test]
+	fn multipage_too_large_test() {
+		let mut packer = super::Packer::new( 256, 256, false, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 300, 10 );
+		assert!( !packer.pack_multipage() );
+	}
+
+	#[test]
+	fn bottom_left_heuristic_test() {
+		let mut packer = super::Packer::new( 256, 256, false, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BottomLeft ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 10, 10 );
+		packer.add( 10, 10 );
+		packer.add( 20, 5 );
+		assert!( packer.pack() );
+		assert_pack_result( &packer.get_results()[0], 0, 0, 10, 10, false, "Test 1" );
+		assert_pack_result( &packer.get_results()[1], 10, 0, 10, 10, false, "Test 2" );
+		assert_pack_result( &packer.get_results()[2], 20, 0, 20, 5, false, "Test 3" );
+	}
+
+	#[test]
+	fn border_and_shape_padding_test() {
+		// no border, so two rects and the gap between them exactly fill the page
+		let mut packer = super::Packer::new( 32, 16, false, false, 0, 2, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BottomLeft ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 15, 16 );
+		packer.add( 15, 16 );
+		assert!( packer.pack() );
+		assert_pack_result( &packer.get_results()[0], 0, 0, 15, 16, false, "Test 1" );
+		assert_pack_result( &packer.get_results()[1], 17, 0, 15, 16, false, "Test 2" );
+
+		// a border no longer leaves space for both
+		let mut packer = super::Packer::new( 32, 16, false, false, 1, 2, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BottomLeft ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 15, 14 );
+		packer.add( 15, 14 );
+		assert!( !packer.pack() );
+
+		// border without any gap between the rects
+		let mut packer = super::Packer::new( 32, 16, false, false, 1, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BottomLeft ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 15, 14 );
+		packer.add( 15, 14 );
+		assert!( packer.pack() );
+		assert_pack_result( &packer.get_results()[0], 1, 1, 15, 14, false, "Test 3" );
+		assert_pack_result( &packer.get_results()[1], 16, 1, 15, 14, false, "Test 4" );
+	}
+
+	#[test]
+	fn size_constraint_test() {
+		let expected = [
+			( super::SizeConstraint::PowerOfTwo, 128, 64 ),
+			( super::SizeConstraint::Any, 101, 61 ),
+			( super::SizeConstraint::MultipleOf4, 104, 64 ),
+			( super::SizeConstraint::Square, 101, 101 )
+		];
+		for ( size_constraint, w, h ) in expected.iter() {
+			let mut packer = super::Packer::new( 1024, 1024, true, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), *size_constraint );
+			packer.add( 101, 61 );
+			assert!( packer.pack_to_fit() );
+			assert_eq!( packer.get_pages()[0].w, *w, "{:?} w", size_constraint );
+			assert_eq!( packer.get_pages()[0].h, *h, "{:?} h", size_constraint );
+		}
+	}
+
+	#[test]
+	fn size_constraint_grow_test() {
+		// grows in smaller steps than doubling, then shrinks to the exact size
+		let mut packer = super::Packer::new( 1000, 1000, true, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::Any );
+		packer.add( 300, 10 );
+		assert!( packer.pack_to_fit() );
+		assert_eq!( packer.get_pages()[0].w, 300 );
+		assert_eq!( packer.get_pages()[0].h, 10 );
+
+		// maximum size is rounded down to a multiple of 4
+		let mut packer = super::Packer::new( 302, 302, true, false, 0, 0, 0, super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ), super::SizeConstraint::MultipleOf4 );
+		packer.add( 301, 10 );
+		assert!( !packer.pack_to_fit() );
+	}
+
+	#[test]
+	fn extrude_test() {
+		let mut packer = super::Packer::new( 256, 256, false, false, 2, 2, 3, super::Algorithm::MaxRects( super::maxrects::Heuristic::BottomLeft ), super::SizeConstraint::PowerOfTwo );
+		packer.add( 10, 10 );
+		packer.add( 10, 10 );
+		assert!( packer.pack() );
+		// each rect is surrounded by 3 pixels of extrusion inside the padding
+		assert_pack_result( &packer.get_results()[0], 5, 5, 10, 10, false, "Test 1" );
+		assert_pack_result( &packer.get_results()[1], 23, 5, 10, 10, false, "Test 2" );
+	}
+
+	#[test]
+	fn all_algorithms_no_overlap_test() {
+		let sizes = vec![ ( 304, 424 ), ( 181, 323 ), ( 178, 286 ), ( 216, 338 ), ( 141, 329 ), ( 264, 318 ), ( 30, 500 ), ( 500, 30 ), ( 64, 64 ), ( 12, 7 ) ];
+		let algorithms = [
+			super::Algorithm::MaxRects( super::maxrects::Heuristic::BestShortSideFit ),
+			super::Algorithm::Skyline( super::skyline::Heuristic::BottomLeft ),
+			super::Algorithm::Guillotine{ heuristic: super::guillotine::Heuristic::BestAreaFit, split_rule: super::guillotine::SplitRule::ShorterLeftoverAxis, merge: false },
+			super::Algorithm::Guillotine{ heuristic: super::guillotine::Heuristic::BestAreaFit, split_rule: super::guillotine::SplitRule::ShorterLeftoverAxis, merge: true }
+		];
+		for algorithm in algorithms.iter().flat_map( |algorithm| algorithm.variants() ) {
+			let mut packer = super::Packer::new( 2048, 2048, false, true, 2, 2, 0, algorithm, super::SizeConstraint::PowerOfTwo );
+			for size in sizes.iter() {
+				packer.add( size.0, size.1 );
+			}
+			assert!( packer.pack(), "{:?}", algorithm );
+			let results = packer.get_results();
+			for ( i, a ) in results.iter().enumerate() {
+				assert!( a.rect.x >= 2 && a.rect.y >= 2 && a.rect.x + a.rect.w <= 2046 && a.rect.y + a.rect.h <= 2046, "{:?} {} out of bounds", algorithm, i );
+				for b in results.iter().skip( i + 1 ) {
+					let overlaps = a.rect.x < b.rect.x + b.rect.w && b.rect.x < a.rect.x + a.rect.w && a.rect.y < b.rect.y + b.rect.h && b.rect.y < a.rect.y + a.rect.h;
+					assert!( !overlaps, "{:?} {} overlaps", algorithm, i );
+				}
+			}
+		}
+	}
+}
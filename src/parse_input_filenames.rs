@@ -2,7 +2,7 @@ use std::path::{ Path, PathBuf };
 use std::fs;
 
 
-fn is_image_file( p: &PathBuf ) -> bool {
+fn is_image_file( p: &Path ) -> bool {
 	match p.extension() {
 		Some( ext ) => {
 			let lc = ext.to_str().unwrap_or( "" ).to_lowercase();
@@ -12,7 +12,7 @@ fn is_image_file( p: &PathBuf ) -> bool {
 	}
 }
 
-fn examine_dir( parent: &PathBuf, mut result: &mut Vec<PathBuf> ) -> Result<(), failure::Error> {
+fn examine_dir( parent: &Path, result: &mut Vec<PathBuf> ) -> Result<(), failure::Error> {
 	for entry_ in fs::read_dir( parent )? {
 		let p = entry_?.path();
 		if p.is_file() {
@@ -20,7 +20,7 @@ fn examine_dir( parent: &PathBuf, mut result: &mut Vec<PathBuf> ) -> Result<(),
 				result.push( p );
 			}
 		} else if p.is_dir() {
-			examine_dir( &p, &mut result )?;
+			examine_dir( &p, result )?;
 		}
 	}
 	Ok(())
@@ -43,7 +43,7 @@ pub fn parse( inputs: Vec<&str> ) -> Result<Vec<PathBuf>, failure::Error> {
 		}
 	}
 
-	if result.len() == 0 {
+	if result.is_empty() {
 		bail!("No valid files found")
 	}
 	
@@ -0,0 +1,177 @@
+use super::inputimage;
+
+// 2D cross product of ( a - o ) and ( b - o ). Positive when o -> a -> b turns anti-clockwise
+fn cross( o: ( f64, f64 ), a: ( f64, f64 ), b: ( f64, f64 ) ) -> f64 {
+	( a.0 - o.0 ) * ( b.1 - o.1 ) - ( a.1 - o.1 ) * ( b.0 - o.0 )
+}
+
+// Convex hull of the given points using the monotone chain algorithm, with no collinear points
+fn convex_hull( points: &mut Vec<( f64, f64 )> ) -> Vec<( f64, f64 )> {
+	points.sort_by( |a, b| a.partial_cmp( b ).unwrap() );
+	points.dedup();
+	if points.len() < 3 {
+		return points.clone();
+	}
+	let mut hull: Vec<( f64, f64 )> = vec!();
+	// one side of the hull going forwards through the points, then the other side going backwards.
+	// The second side must not remove points from the first
+	for point in points.iter() {
+		while hull.len() >= 2 && cross( hull[hull.len() - 2], hull[hull.len() - 1], *point ) <= 0.0 {
+			hull.pop();
+		}
+		hull.push( *point );
+	}
+	let first_side_len = hull.len() + 1;
+	for point in points.iter().rev().skip( 1 ) {
+		while hull.len() >= first_side_len && cross( hull[hull.len() - 2], hull[hull.len() - 1], *point ) <= 0.0 {
+			hull.pop();
+		}
+		hull.push( *point );
+	}
+	// the last point is the first point again
+	hull.pop();
+	hull
+}
+
+// Point where the line through a and b meets the line through c and d, as long as it lies beyond b and beyond c.
+// Replacing the edge b -> c of a convex polygon with this point grows the polygon by the triangle b, point, c
+fn extend_edges( a: ( f64, f64 ), b: ( f64, f64 ), c: ( f64, f64 ), d: ( f64, f64 ) ) -> Option<( f64, f64 )> {
+	let ab = ( b.0 - a.0, b.1 - a.1 );
+	let dc = ( c.0 - d.0, c.1 - d.1 );
+	let denominator = ab.0 * dc.1 - ab.1 * dc.0;
+	if denominator.abs() < 1e-9 {
+		return None;
+	}
+	let t = ( ( d.0 - a.0 ) * dc.1 - ( d.1 - a.1 ) * dc.0 ) / denominator;
+	let u = ( ( d.0 - a.0 ) * ab.1 - ( d.1 - a.1 ) * ab.0 ) / denominator;
+	if t <= 1.0 || u <= 1.0 {
+		return None;
+	}
+	Some( ( a.0 + ab.0 * t, a.1 + ab.1 * t ) )
+}
+
+// Reduces the vertex count of a convex polygon to max_vertices while still containing the original polygon and staying within
+// the w x h rect, by repeatedly removing the edge which adds the least area. Returns None if it cannot be reduced far enough
+fn reduce_vertices( polygon: &[( f64, f64 )], max_vertices: usize, w: f64, h: f64 ) -> Option<Vec<( f64, f64 )>> {
+	let mut polygon = polygon.to_vec();
+	while polygon.len() > max_vertices {
+		let count = polygon.len();
+		let mut best: Option<( usize, ( f64, f64 ), f64 )> = None;
+		for i in 0..count {
+			let a = polygon[( i + count - 1 ) % count];
+			let b = polygon[i];
+			let c = polygon[( i + 1 ) % count];
+			let d = polygon[( i + 2 ) % count];
+			let point = match extend_edges( a, b, c, d ) {
+				Some( point ) => point,
+				None => continue
+			};
+			if point.0 < 0.0 || point.1 < 0.0 || point.0 > w || point.1 > h {
+				continue;
+			}
+			let added_area = cross( b, point, c ).abs() / 2.0;
+			if best.is_none_or( |( _, _, best_area )| added_area < best_area ) {
+				best = Some( ( i, point, added_area ) );
+			}
+		}
+		let ( index, point, _ ) = best?;
+		polygon[index] = point;
+		polygon.remove( ( index + 1 ) % count );
+	}
+	Some( polygon )
+}
+
+// Calculates a convex polygon with at most max_vertices vertices which contains every pixel of the trimmed area with an alpha
+// value above threshold. Coordinates are relative to the top left of the trimmed area. Falls back to the trimmed rect if there
+// are no such pixels or the hull cannot be reduced to max_vertices
+pub fn outline( img: &inputimage::InputImage, threshold: u8, max_vertices: usize ) -> Vec<( f32, f32 )> {
+	let rect = vec![ ( 0.0, 0.0 ), ( img.vw as f32, 0.0 ), ( img.vw as f32, img.vh as f32 ), ( 0.0, img.vh as f32 ) ];
+	// only the leftmost and rightmost pixels of each row can be on the hull. Use the corners of those pixels
+	let mut points: Vec<( f64, f64 )> = vec!();
+	for row in 0..img.vh {
+		let start = ( ( img.vy + row ) * img.w + img.vx ) as usize;
+		let alphas: Vec<u8> = ( 0..img.vw as usize ).map( |col| img.data[( start + col ) * 4 + 3] ).collect();
+		let left = alphas.iter().position( |alpha| *alpha > threshold );
+		let right = alphas.iter().rposition( |alpha| *alpha > threshold );
+		if let ( Some( left ), Some( right ) ) = ( left, right ) {
+			let y = row as f64;
+			points.push( ( left as f64, y ) );
+			points.push( ( left as f64, y + 1.0 ) );
+			points.push( ( right as f64 + 1.0, y ) );
+			points.push( ( right as f64 + 1.0, y + 1.0 ) );
+		}
+	}
+	let hull = convex_hull( &mut points );
+	if hull.len() < 3 {
+		return rect;
+	}
+	match reduce_vertices( &hull, max_vertices, img.vw as f64, img.vh as f64 ) {
+		Some( polygon ) => polygon.iter().map( |( x, y )| ( *x as f32, *y as f32 ) ).collect(),
+		None => rect
+	}
+}
+
+// Splits a convex polygon with vertex_count vertices into triangles of vertex indices
+pub fn triangulate( vertex_count: usize ) -> Vec<[usize; 3]> {
+	( 1..vertex_count.saturating_sub( 1 ) ).map( |i| [ 0, i, i + 1 ] ).collect()
+}
+
+#[cfg(test)]
+mod test_polygon {
+	fn create_image( w: i32, h: i32, opaque: &[( i32, i32 )] ) -> super::inputimage::InputImage {
+		let mut data = vec![ 0; ( w * h * 4 ) as usize ];
+		for ( x, y ) in opaque.iter() {
+			data[( ( y * w + x ) * 4 + 3 ) as usize] = 255;
+		}
+		super::inputimage::InputImage::new( std::path::Path::new( "test.png" ), w, h, data )
+	}
+
+	// True if the point is inside or on the edge of the convex polygon
+	fn polygon_contains( polygon: &[( f32, f32 )], point: ( f64, f64 ) ) -> bool {
+		( 0..polygon.len() ).all( |i| {
+			let a = polygon[i];
+			let b = polygon[( i + 1 ) % polygon.len()];
+			super::cross( ( a.0 as f64, a.1 as f64 ), ( b.0 as f64, b.1 as f64 ), point ) >= -1e-4
+		} )
+	}
+
+	#[test]
+	fn diamond_test() {
+		// single pixel wide diamond in a 5x5 image
+		let image = create_image( 5, 5, &[ ( 2, 0 ), ( 1, 1 ), ( 3, 1 ), ( 0, 2 ), ( 4, 2 ), ( 1, 3 ), ( 3, 3 ), ( 2, 4 ) ] );
+		let polygon = super::outline( &image, 0, 8 );
+		assert_eq!( polygon.len(), 8 );
+		for ( x, y ) in [ ( 2.5, 0.0 ), ( 0.0, 2.5 ), ( 5.0, 2.5 ), ( 2.5, 5.0 ) ] {
+			assert!( polygon_contains( &polygon, ( x, y ) ), "{}x{}", x, y );
+		}
+		assert!( !polygon_contains( &polygon, ( 0.1, 0.1 ) ) );
+	}
+
+	#[test]
+	fn reduce_vertices_test() {
+		let image = create_image( 5, 5, &[ ( 2, 0 ), ( 1, 1 ), ( 3, 1 ), ( 0, 2 ), ( 4, 2 ), ( 1, 3 ), ( 3, 3 ), ( 2, 4 ) ] );
+		let polygon = super::outline( &image, 0, 4 );
+		assert_eq!( polygon.len(), 4 );
+		// every corner of every opaque pixel is still inside
+		for ( x, y ) in [ ( 2, 0 ), ( 1, 1 ), ( 3, 1 ), ( 0, 2 ), ( 4, 2 ), ( 1, 3 ), ( 3, 3 ), ( 2, 4 ) ] {
+			for ( cx, cy ) in [ ( 0, 0 ), ( 1, 0 ), ( 0, 1 ), ( 1, 1 ) ] {
+				assert!( polygon_contains( &polygon, ( ( x + cx ) as f64, ( y + cy ) as f64 ) ), "{}x{}", x + cx, y + cy );
+			}
+		}
+		for ( x, y ) in polygon.iter() {
+			assert!( *x >= 0.0 && *x <= 5.0 && *y >= 0.0 && *y <= 5.0 );
+		}
+	}
+
+	#[test]
+	fn empty_image_test() {
+		let image = create_image( 3, 2, &[] );
+		assert_eq!( super::outline( &image, 0, 8 ), vec![ ( 0.0, 0.0 ), ( 3.0, 0.0 ), ( 3.0, 2.0 ), ( 0.0, 2.0 ) ] );
+	}
+
+	#[test]
+	fn triangulate_test() {
+		assert_eq!( super::triangulate( 3 ), vec![ [ 0, 1, 2 ] ] );
+		assert_eq!( super::triangulate( 5 ), vec![ [ 0, 1, 2 ], [ 0, 2, 3 ], [ 0, 3, 4 ] ] );
+	}
+}
@@ -23,4 +23,22 @@ impl Clone for Rect {
 	fn clone(&self) -> Rect {
 		*self
 	}
-}
\ No newline at end of file
+}
+
+// Direction sub images are turned when they are rotated to fit in the atlas. Runtimes disagree, libGDX expects anti-clockwise
+// while TexturePacker style formats expect clockwise
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Rotation {
+	AntiClockwise,
+	Clockwise
+}
+
+impl Rotation {
+	// Position of the point x, y in a w x h rect once the rect has been rotated
+	pub fn transform( &self, x: f32, y: f32, w: f32, h: f32 ) -> ( f32, f32 ) {
+		match self {
+			Rotation::AntiClockwise => ( y, w - x ),
+			Rotation::Clockwise => ( h - y, x )
+		}
+	}
+}
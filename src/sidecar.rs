@@ -0,0 +1,131 @@
+use std::path::{ Path, PathBuf };
+
+// A single pivot coordinate, either relative to the size of the sub image or in pixels
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PivotCoord {
+	Normalised( f32 ),
+	Pixels( f32 )
+}
+
+impl PivotCoord {
+	fn parse( value: &str ) -> Option<PivotCoord> {
+		let value = value.trim();
+		match value.strip_suffix( "px" ) {
+			Some( pixels ) => pixels.trim().parse::<f32>().ok().map( PivotCoord::Pixels ),
+			None => value.parse::<f32>().ok().map( PivotCoord::Normalised )
+		}
+	}
+
+	// Position in pixels along a sub image dimension of the given size
+	pub fn resolve( &self, size: i32 ) -> f32 {
+		match self {
+			PivotCoord::Normalised( value ) => value * size as f32,
+			PivotCoord::Pixels( value ) => *value
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Pivot {
+	pub x: PivotCoord,
+	pub y: PivotCoord
+}
+
+impl Pivot {
+	// Parses "x,y" where each coordinate is either normalised ( 0.5 ) or in pixels ( 16px )
+	pub fn parse( value: &str ) -> Result<Pivot, failure::Error> {
+		let coords: Vec<&str> = value.split( ',' ).collect();
+		if coords.len() != 2 {
+			bail!( "Invalid pivot '{}', expected x,y", value );
+		}
+		match ( PivotCoord::parse( coords[0] ), PivotCoord::parse( coords[1] ) ) {
+			( Some( x ), Some( y ) ) => Ok( Pivot{ x, y } ),
+			_ => bail!( "Invalid pivot '{}', coordinates must be numbers, optionally followed by px", value )
+		}
+	}
+}
+
+// Per sub image settings read from a sidecar file next to the image, named after the image with an extra .json or .toml
+// extension, e.g. hero.png.toml
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteSettings {
+	pub pivot: Option<String>,
+	// left, top, right, bottom
+	pub nine_slice: Option<[i32; 4]>,
+	// frames per second and looping of the animation the sub image is a frame of
+	pub fps: Option<f32>,
+	#[serde(rename = "loop")]
+	pub looping: Option<bool>
+}
+
+impl SpriteSettings {
+	fn sidecar_path( image_path: &Path, extension: &str ) -> PathBuf {
+		let mut filename = image_path.as_os_str().to_owned();
+		filename.push( "." );
+		filename.push( extension );
+		PathBuf::from( filename )
+	}
+
+	// Loads the sidecar settings for the given image, or the defaults if it has no sidecar file
+	pub fn load( image_path: &Path ) -> Result<SpriteSettings, failure::Error> {
+		let json_path = SpriteSettings::sidecar_path( image_path, "json" );
+		if json_path.exists() {
+			let contents = std::fs::read_to_string( &json_path )?;
+			return serde_json::from_str( &contents ).map_err( |e| format_err!( "Failed to parse {:?}: {}", json_path, e ) );
+		}
+		let toml_path = SpriteSettings::sidecar_path( image_path, "toml" );
+		if toml_path.exists() {
+			let contents = std::fs::read_to_string( &toml_path )?;
+			return toml::from_str( &contents ).map_err( |e| format_err!( "Failed to parse {:?}: {}", toml_path, e ) );
+		}
+		Ok( SpriteSettings::default() )
+	}
+
+	// Pivot for the sub image, falling back to default_pivot if the sidecar doesn't set one
+	pub fn pivot( &self, default_pivot: Pivot ) -> Result<Pivot, failure::Error> {
+		match &self.pivot {
+			Some( pivot ) => Pivot::parse( pivot ),
+			None => Ok( default_pivot )
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_sidecar {
+	use super::{ Pivot, PivotCoord };
+
+	#[test]
+	fn parse_pivot_test() {
+		assert_eq!( Pivot::parse( "0.5,1" ).unwrap(), Pivot{ x: PivotCoord::Normalised( 0.5 ), y: PivotCoord::Normalised( 1.0 ) } );
+		assert_eq!( Pivot::parse( "16px, 0.25" ).unwrap(), Pivot{ x: PivotCoord::Pixels( 16.0 ), y: PivotCoord::Normalised( 0.25 ) } );
+		assert!( Pivot::parse( "0.5" ).is_err() );
+		assert!( Pivot::parse( "a,b" ).is_err() );
+		assert_eq!( PivotCoord::Normalised( 0.25 ).resolve( 200 ), 50.0 );
+		assert_eq!( PivotCoord::Pixels( 12.0 ).resolve( 200 ), 12.0 );
+	}
+
+	#[test]
+	fn load_sidecar_test() {
+		let centre = Pivot{ x: PivotCoord::Normalised( 0.5 ), y: PivotCoord::Normalised( 0.5 ) };
+		let dir = std::env::temp_dir().join( format!( "atlasbuilder_sidecar_test_{}", std::process::id() ) );
+		std::fs::create_dir_all( &dir ).unwrap();
+		let toml_image = dir.join( "a.png" );
+		std::fs::write( dir.join( "a.png.toml" ), "pivot = \"0.5,1\"\n" ).unwrap();
+		let json_image = dir.join( "b.png" );
+		std::fs::write( dir.join( "b.png.json" ), "{ \"pivot\": \"4px,8px\", \"nine_slice\": [ 1, 2, 3, 4 ], \"fps\": 12, \"loop\": false }" ).unwrap();
+		let broken_image = dir.join( "c.png" );
+		std::fs::write( dir.join( "c.png.json" ), "{ \"pivto\": \"4px,8px\" }" ).unwrap();
+
+		let settings = super::SpriteSettings::load( &toml_image ).unwrap();
+		assert_eq!( settings.pivot( centre ).unwrap(), Pivot::parse( "0.5,1" ).unwrap() );
+		let settings = super::SpriteSettings::load( &json_image ).unwrap();
+		assert_eq!( settings.pivot( centre ).unwrap(), Pivot::parse( "4px,8px" ).unwrap() );
+		assert_eq!( settings.nine_slice, Some( [ 1, 2, 3, 4 ] ) );
+		assert_eq!( ( settings.fps, settings.looping ), ( Some( 12.0 ), Some( false ) ) );
+		assert!( super::SpriteSettings::load( &broken_image ).is_err() );
+		let settings = super::SpriteSettings::load( &dir.join( "d.png" ) ).unwrap();
+		assert_eq!( settings.pivot( centre ).unwrap(), centre );
+		std::fs::remove_dir_all( &dir ).unwrap();
+	}
+}
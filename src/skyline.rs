@@ -0,0 +1,215 @@
+use std::cmp;
+use super::shapes;
+use super::packer;
+
+// Rules used to choose which skyline segment a sub image is placed on
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Heuristic {
+	BottomLeft,
+	MinWaste
+}
+
+impl Heuristic {
+	pub const ALL: [Heuristic; 2] = [
+		Heuristic::BottomLeft,
+		Heuristic::MinWaste
+	];
+
+	pub fn name( &self ) -> &'static str {
+		match self {
+			Heuristic::BottomLeft => "bottom-left",
+			Heuristic::MinWaste => "min-waste"
+		}
+	}
+
+	pub fn from_name( name: &str ) -> Option<Heuristic> {
+		Heuristic::ALL.iter().copied().find( |heuristic| heuristic.name() == name )
+	}
+}
+
+// Horizontal segment of the skyline. Everything above y between x and x + w has been used
+#[derive(Clone, Copy)]
+struct SkylineNode {
+	x: i32,
+	y: i32,
+	w: i32
+}
+
+// Skyline bin packer. Only tracks the lowest used edge across the page, so is much faster than MaxRects
+// at the cost of being unable to fill gaps left underneath placed rects
+pub struct SkylineBin {
+	heuristic: Heuristic,
+	bounds: shapes::Rect,
+	skyline: Vec<SkylineNode>
+}
+
+impl SkylineBin {
+	pub fn new( bounds: shapes::Rect, heuristic: Heuristic ) -> SkylineBin {
+		SkylineBin{
+			heuristic,
+			bounds,
+			skyline: vec![ SkylineNode{ x: bounds.x, y: bounds.y, w: bounds.w } ]
+		}
+	}
+
+	// Returns the y position a w x h rect would sit at if its left edge was placed at the start of skyline node index
+	fn rect_fits( &self, index: usize, w: i32, h: i32 ) -> Option<i32> {
+		let x = self.skyline[index].x;
+		if x + w > self.bounds.x + self.bounds.w {
+			return None;
+		}
+		let mut width_left = w;
+		let mut y = self.skyline[index].y;
+		let mut i = index;
+		while width_left > 0 {
+			y = cmp::max( y, self.skyline[i].y );
+			if y + h > self.bounds.y + self.bounds.h {
+				return None;
+			}
+			width_left -= self.skyline[i].w;
+			i += 1;
+		}
+		Some( y )
+	}
+
+	// Area left unusable underneath a w wide rect placed at y on top of skyline node index
+	fn wasted_area( &self, index: usize, w: i32, y: i32 ) -> i32 {
+		let rect_right = self.skyline[index].x + w;
+		let mut wasted = 0;
+		for node in self.skyline[index..].iter() {
+			if node.x >= rect_right {
+				break;
+			}
+			let right_side = cmp::min( rect_right, node.x + node.w );
+			wasted += ( right_side - node.x ) * ( y - node.y );
+		}
+		wasted
+	}
+
+	// Scores placing a w x h rect at y on top of skyline node index. Lower scores are better
+	fn score_placement( &self, index: usize, w: i32, h: i32, y: i32 ) -> ( i32, i32 ) {
+		match self.heuristic {
+			Heuristic::BottomLeft => ( y + h, self.skyline[index].w ),
+			Heuristic::MinWaste => ( self.wasted_area( index, w, y ), y + h )
+		}
+	}
+
+	fn find_best_position( &self, w: i32, h: i32, allow_rotate: bool ) -> Option<( usize, packer::PackResult )> {
+		let mut best_score = ( i32::MAX, i32::MAX );
+		let mut best: Option<( usize, packer::PackResult )> = None;
+		for index in 0..self.skyline.len() {
+			if let Some( y ) = self.rect_fits( index, w, h ) {
+				let score = self.score_placement( index, w, h, y );
+				if score < best_score {
+					best_score = score;
+					best = Some( ( index, packer::PackResult{
+						rect: shapes::Rect{ x: self.skyline[index].x, y, w, h }, rotated: false, page: 0
+					} ) );
+				}
+			}
+			if allow_rotate {
+				if let Some( y ) = self.rect_fits( index, h, w ) {
+					let score = self.score_placement( index, h, w, y );
+					if score < best_score {
+						best_score = score;
+						best = Some( ( index, packer::PackResult{
+							rect: shapes::Rect{ x: self.skyline[index].x, y, w: h, h: w }, rotated: true, page: 0
+						} ) );
+					}
+				}
+			}
+		}
+		best
+	}
+
+	// Raises the skyline to the bottom edge of a rect placed on top of skyline node index
+	fn add_level( &mut self, index: usize, rect: &shapes::Rect ) {
+		self.skyline.insert( index, SkylineNode{ x: rect.x, y: rect.y + rect.h, w: rect.w } );
+
+		// shrink or remove the nodes now covered by the new one
+		let i = index + 1;
+		while i < self.skyline.len() {
+			let previous = self.skyline[i - 1];
+			let node = &mut self.skyline[i];
+			if node.x >= previous.x + previous.w {
+				break;
+			}
+			let shrink = previous.x + previous.w - node.x;
+			node.x += shrink;
+			node.w -= shrink;
+			if node.w > 0 {
+				break;
+			}
+			self.skyline.remove( i );
+		}
+
+		// merge neighbouring nodes at the same height
+		let mut i = 0;
+		while i + 1 < self.skyline.len() {
+			if self.skyline[i].y == self.skyline[i + 1].y {
+				self.skyline[i].w += self.skyline[i + 1].w;
+				self.skyline.remove( i + 1 );
+			} else {
+				i += 1;
+			}
+		}
+	}
+}
+
+impl packer::BinPacker for SkylineBin {
+	fn insert( &mut self, w: i32, h: i32, allow_rotate: bool ) -> Option<packer::PackResult> {
+		let ( index, result ) = self.find_best_position( w, h, allow_rotate )?;
+		self.add_level( index, &result.rect );
+		Some( result )
+	}
+}
+
+#[cfg(test)]
+mod test_skyline {
+	use super::packer::BinPacker;
+
+	fn assert_rect( result: &super::packer::PackResult, x: i32, y: i32, w: i32, h: i32, message: &str ) {
+		assert_eq!( result.rect.x, x, "{} - x", message );
+		assert_eq!( result.rect.y, y, "{} - y", message );
+		assert_eq!( result.rect.w, w, "{} - w", message );
+		assert_eq!( result.rect.h, h, "{} - h", message );
+	}
+
+	#[test]
+	fn bottom_left_test() {
+		let mut bin = super::SkylineBin::new( super::shapes::Rect{ x: 0, y: 0, w: 100, h: 100 }, super::Heuristic::BottomLeft );
+		assert_rect( &bin.insert( 50, 20, false ).unwrap(), 0, 0, 50, 20, "Test 1" );
+		assert_rect( &bin.insert( 30, 10, false ).unwrap(), 50, 0, 30, 10, "Test 2" );
+		// lowest position is in the gap at the right hand edge
+		assert_rect( &bin.insert( 20, 40, false ).unwrap(), 80, 0, 20, 40, "Test 3" );
+		assert_rect( &bin.insert( 30, 30, false ).unwrap(), 50, 10, 30, 30, "Test 4" );
+		assert_eq!( bin.skyline.len(), 2 );
+		assert!( bin.insert( 100, 100, false ).is_none() );
+	}
+
+	#[test]
+	fn min_waste_test() {
+		let skyline = vec![
+			super::SkylineNode{ x: 0, y: 30, w: 50 },
+			super::SkylineNode{ x: 50, y: 0, w: 10 },
+			super::SkylineNode{ x: 60, y: 5, w: 40 }
+		];
+		// bottom left places the rect as low as possible, leaving a gap underneath it
+		let mut bin = super::SkylineBin::new( super::shapes::Rect{ x: 0, y: 0, w: 100, h: 100 }, super::Heuristic::BottomLeft );
+		bin.skyline = skyline.clone();
+		assert_rect( &bin.insert( 50, 10, false ).unwrap(), 50, 5, 50, 10, "Bottom left" );
+
+		// min waste places it flush on top of the left hand node instead
+		let mut bin = super::SkylineBin::new( super::shapes::Rect{ x: 0, y: 0, w: 100, h: 100 }, super::Heuristic::MinWaste );
+		bin.skyline = skyline;
+		assert_rect( &bin.insert( 50, 10, false ).unwrap(), 0, 30, 50, 10, "Min waste" );
+	}
+
+	#[test]
+	fn rotate_test() {
+		let mut bin = super::SkylineBin::new( super::shapes::Rect{ x: 0, y: 0, w: 100, h: 50 }, super::Heuristic::BottomLeft );
+		let result = bin.insert( 10, 80, true ).unwrap();
+		assert!( result.rotated );
+		assert_rect( &result, 0, 0, 80, 10, "Rotated" );
+	}
+}
@@ -0,0 +1,67 @@
+/* Generated by atlasbuilder - https://github.com/peteward44/atlasbuilder-rust */
+#ifndef OUT_ATLAS_H
+#define OUT_ATLAS_H
+
+#ifndef ATLASBUILDER_FRAME_DEFINED
+#define ATLASBUILDER_FRAME_DEFINED
+typedef struct atlas_frame {
+	const char *name;
+	int page;
+	/* rect in the atlas image, rotated 90 degrees if rotated is set */
+	int x, y, w, h;
+	/* offset of the trimmed rect within the original image, and the original image size */
+	int trimmed_x, trimmed_y;
+	int source_w, source_h;
+	int rotated;
+} atlas_frame;
+#endif
+
+#define OUT_PAGE_0_IMAGE "out.png"
+
+#define OUT_SPRITE_INPUT3_PAGE 0
+#define OUT_SPRITE_INPUT3_X 2
+#define OUT_SPRITE_INPUT3_Y 2
+#define OUT_SPRITE_INPUT3_W 200
+#define OUT_SPRITE_INPUT3_H 200
+#define OUT_SPRITE_INPUT3_TRIMMED_X 0
+#define OUT_SPRITE_INPUT3_TRIMMED_Y 0
+#define OUT_SPRITE_INPUT3_SOURCE_W 200
+#define OUT_SPRITE_INPUT3_SOURCE_H 200
+#define OUT_SPRITE_INPUT3_ROTATED 0
+
+#define OUT_SPRITE_INPUT2_PAGE 0
+#define OUT_SPRITE_INPUT2_X 2
+#define OUT_SPRITE_INPUT2_Y 204
+#define OUT_SPRITE_INPUT2_W 200
+#define OUT_SPRITE_INPUT2_H 200
+#define OUT_SPRITE_INPUT2_TRIMMED_X 0
+#define OUT_SPRITE_INPUT2_TRIMMED_Y 0
+#define OUT_SPRITE_INPUT2_SOURCE_W 200
+#define OUT_SPRITE_INPUT2_SOURCE_H 200
+#define OUT_SPRITE_INPUT2_ROTATED 0
+
+#define OUT_SPRITE_INPUT1_TRIM_PAGE 0
+#define OUT_SPRITE_INPUT1_TRIM_X 204
+#define OUT_SPRITE_INPUT1_TRIM_Y 2
+#define OUT_SPRITE_INPUT1_TRIM_W 112
+#define OUT_SPRITE_INPUT1_TRIM_H 147
+#define OUT_SPRITE_INPUT1_TRIM_TRIMMED_X 24
+#define OUT_SPRITE_INPUT1_TRIM_TRIMMED_Y 31
+#define OUT_SPRITE_INPUT1_TRIM_SOURCE_W 200
+#define OUT_SPRITE_INPUT1_TRIM_SOURCE_H 200
+#define OUT_SPRITE_INPUT1_TRIM_ROTATED 1
+
+enum out_sprite_id {
+	OUT_SPRITE_INPUT3,
+	OUT_SPRITE_INPUT2,
+	OUT_SPRITE_INPUT1_TRIM,
+	OUT_SPRITE_COUNT
+};
+
+static const atlas_frame OUT_FRAMES[OUT_SPRITE_COUNT] = {
+	{ "input3.png", 0, 2, 2, 200, 200, 0, 0, 200, 200, 0 },
+	{ "input2.png", 0, 2, 204, 200, 200, 0, 0, 200, 200, 0 },
+	{ "input1_trim.png", 0, 204, 2, 112, 147, 24, 31, 200, 200, 1 }
+};
+
+#endif
@@ -0,0 +1,41 @@
+// Generated by atlasbuilder - https://github.com/peteward44/atlasbuilder-rust
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub name: &'static str,
+    pub page: usize,
+    /// Rect in the atlas image, rotated 90 degrees if `rotated` is set
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    /// Offset of the trimmed rect within the original image, and the original image size
+    pub trimmed_x: u32,
+    pub trimmed_y: u32,
+    pub source_w: u32,
+    pub source_h: u32,
+    pub rotated: bool,
+}
+
+pub const PAGES: [&str; 1] = ["out.png"];
+
+pub const INPUT3: Frame = Frame { name: "input3.png", page: 0, x: 2, y: 2, w: 200, h: 200, trimmed_x: 0, trimmed_y: 0, source_w: 200, source_h: 200, rotated: false };
+pub const INPUT2: Frame = Frame { name: "input2.png", page: 0, x: 2, y: 204, w: 200, h: 200, trimmed_x: 0, trimmed_y: 0, source_w: 200, source_h: 200, rotated: false };
+pub const INPUT1_TRIM: Frame = Frame { name: "input1_trim.png", page: 0, x: 204, y: 2, w: 112, h: 147, trimmed_x: 24, trimmed_y: 31, source_w: 200, source_h: 200, rotated: true };
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrameId {
+    Input3,
+    Input2,
+    Input1Trim,
+}
+
+pub const COUNT: usize = 3;
+
+pub const FRAMES: [Frame; COUNT] = [INPUT3, INPUT2, INPUT1_TRIM];
+
+impl FrameId {
+    pub const fn frame(self) -> &'static Frame {
+        &FRAMES[self as usize]
+    }
+}
@@ -35,9 +35,37 @@ mod integration_tests {
 			}
 		}
 
-		return true;
+		true
 	}
 
+    // Packs the inputs with a predefined meta template and checks the atlas and meta data against the fixture results
+    fn assert_template_matches_fixture(template: &str, meta_filename: &str, inputs: &[&str], fixture_dir: &str) {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        let result_path = test_data_path.join("results").join(fixture_dir);
+
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_meta = temp_dir.child(meta_filename);
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_meta.to_owned())
+            .arg("--meta-template")
+            .arg(template)
+            .args(inputs.iter().map(|input| test_data_path.join("input").join(input)))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_image.assert(predicate::path::exists());
+		assert!(are_pngs_equal(out_image.path(), result_path.join("out.png").as_path()), "{} atlas differs from {}", template, fixture_dir);
+        out_meta.assert(predicate::path::exists());
+        out_meta.assert(predicate::path::eq_file(result_path.join(meta_filename)));
+    }
+
     #[test]
     fn test_help() {
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
@@ -86,7 +114,7 @@ mod integration_tests {
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let empty_input_dir = temp_dir.child("empty_input_dir");
-        std::fs::create_dir_all(empty_input_dir.to_owned()).unwrap();
+        std::fs::create_dir_all(&empty_input_dir).unwrap();
         empty_input_dir.assert(predicate::path::exists());
         let assert = cmd
             .arg(empty_input_dir.to_owned())
@@ -102,16 +130,16 @@ mod integration_tests {
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let empty_input_dir = temp_dir.child("empty_input_dir");
-        std::fs::create_dir_all(empty_input_dir.to_owned()).unwrap();
+        std::fs::create_dir_all(&empty_input_dir).unwrap();
         empty_input_dir.assert(predicate::path::exists());
         let file1 = empty_input_dir.child("text.txt");
-        std::fs::write(file1.to_owned(), "dolor sit").unwrap();
+        std::fs::write(&file1, "dolor sit").unwrap();
         file1.assert(predicate::path::exists());
         let file2 = empty_input_dir.child("tiff_image.tiff");
-        std::fs::write(file2.to_owned(), "not a real image").unwrap();
+        std::fs::write(&file2, "not a real image").unwrap();
         file2.assert(predicate::path::exists());
         let file3 = empty_input_dir.child("binary.dat");
-        std::fs::write(file3.to_owned(), "not a real dat").unwrap();
+        std::fs::write(&file3, "not a real dat").unwrap();
         file3.assert(predicate::path::exists());
 
         let assert = cmd
@@ -145,7 +173,7 @@ mod integration_tests {
             .code(0);
 
         out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/single_input_file_result/out.png").as_path());
+		assert!(are_pngs_equal(out_image.path(), test_data_path.join("results/single_input_file_result/out.png").as_path()));
         out_json.assert(predicate::path::exists());
         out_json.assert(predicate::path::eq_file(test_data_path.join("results/single_input_file_result/out.json")));
     }
@@ -174,27 +202,99 @@ mod integration_tests {
             .code(0);
 
         out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/multi_input_file_result/out.png").as_path());
+		assert!(are_pngs_equal(out_image.path(), test_data_path.join("results/multi_input_file_result/out.png").as_path()));
         out_json.assert(predicate::path::exists());
         out_json.assert(predicate::path::eq_file(test_data_path.join("results/multi_input_file_result/out.json")));
     }
 
     #[test]
     fn test_json_array_template() {
+        assert_template_matches_fixture("json-array", "out.json", &["input1.png", "input2.png", "input3.png"], "template_json_array_file_result");
+    }
+
+    #[test]
+    fn test_json_hash_template() {
+        assert_template_matches_fixture("json-hash", "out.json", &["input1.png", "input2.png", "input3.png"], "template_json_hash_file_result");
+    }
+
+    #[test]
+    fn test_toml_template() {
+        assert_template_matches_fixture("toml", "out.toml", &["input1.png", "input2.png", "input3.png"], "template_toml_file_result");
+    }
+
+    #[test]
+    fn test_yaml_template() {
+        assert_template_matches_fixture("yaml", "out.yaml", &["input1.png", "input2.png", "input3.png"], "template_yaml_file_result");
+    }
+
+    #[test]
+    fn test_xml_template() {
+        assert_template_matches_fixture("xml", "out.xml", &["input1.png", "input2.png", "input3.png"], "template_xml_file_result");
+    }
+
+    #[test]
+    fn test_libgdx_template() {
+        assert_template_matches_fixture("libgdx", "out.atlas", &["input1_trim.png", "input2.png", "input3.png"], "template_libgdx_file_result");
+    }
+
+    #[test]
+    fn test_libgdx_template_multiple_pages() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let out_image = temp_dir.child("out.png");
+        let out_atlas = temp_dir.child("out.atlas");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_atlas.to_owned())
+            .arg("--meta-template")
+            .arg("libgdx")
+            .arg("--width")
+            .arg("256")
+            .arg("--height")
+            .arg("256")
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // each page is listed followed by its regions
+        out_atlas.assert(predicate::str::contains("\nout-0.png\nsize: 256,256\n"));
+        out_atlas.assert(predicate::str::contains("\nout-1.png\nsize: 256,256\n"));
+        out_atlas.assert(predicate::str::contains("repeat: none\ninput3\n"));
+        out_atlas.assert(predicate::str::contains("repeat: none\ninput2\n"));
+    }
+
+    #[test]
+    fn test_cocos2d_plist_template() {
+        assert_template_matches_fixture("cocos2d-plist", "out.plist", &["input1_trim.png", "input2.png", "input3.png"], "template_cocos2d_plist_file_result");
+    }
+
+    #[test]
+    fn test_godot_output() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("atlas/out.png");
         let out_json = temp_dir.child("out.json");
+        let godot_dir = temp_dir.child("sprites");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let assert = cmd
             .arg("--image-output")
             .arg(out_image.to_owned())
             .arg("--meta-output")
             .arg(out_json.to_owned())
-            .arg("--meta-template")
-            .arg("json-array")
-            .arg(test_data_path.join("input/input1.png"))
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg("--godot-output")
+            .arg(godot_dir.to_owned())
+            .arg("--godot-sprite-frames")
+            .arg(test_data_path.join("input/input1_trim.png"))
             .arg(test_data_path.join("input/input2.png"))
             .arg(test_data_path.join("input/input3.png"))
             .assert();
@@ -202,77 +302,188 @@ mod integration_tests {
             .success()
             .code(0);
 
-        out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/template_json_array_file_result/out.png").as_path());
-        out_json.assert(predicate::path::exists());
-        out_json.assert(predicate::path::eq_file(test_data_path.join("results/template_json_array_file_result/out.json")));
+        // trimmed sub images have the transparent edges as the margin, and rotation is disabled
+        let trimmed = godot_dir.child("input1_trim.tres");
+        trimmed.assert(predicate::str::contains("[ext_resource type=\"Texture2D\" path=\"res://atlas/out.png\" id=\"1\"]"));
+        trimmed.assert(predicate::str::contains("region = Rect2(204, 2, 112, 147)"));
+        trimmed.assert(predicate::str::contains("margin = Rect2(24, 31, 88, 53)"));
+        godot_dir.child("input2.tres").assert(predicate::str::contains("margin = Rect2(0, 0, 0, 0)"));
+        // input2 and input3 are frames 2 and 3 of the animation "input"
+        let sprite_frames = godot_dir.child("sprite_frames.tres");
+        sprite_frames.assert(predicate::str::contains("[ext_resource type=\"AtlasTexture\" path=\"res://sprites/input2.tres\""));
+        sprite_frames.assert(predicate::str::contains("\"name\": &\"input\""));
+        sprite_frames.assert(predicate::str::contains("\"name\": &\"input1_trim\""));
     }
 
     #[test]
-    fn test_json_hash_template() {
+    fn test_godot_output_same_names() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let hero_a = temp_dir.child("a/hero.png");
+        hero_a.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        let hero_b = temp_dir.child("b/hero.png");
+        hero_b.write_file(test_data_path.join("input/input1_trim.png").as_path()).unwrap();
         let out_image = temp_dir.child("out.png");
         let out_json = temp_dir.child("out.json");
+        let godot_dir = temp_dir.child("sprites");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let assert = cmd
             .arg("--image-output")
             .arg(out_image.to_owned())
             .arg("--meta-output")
             .arg(out_json.to_owned())
-            .arg("--meta-template")
-            .arg("json-hash")
-            .arg(test_data_path.join("input/input1.png"))
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg("--godot-output")
+            .arg(godot_dir.to_owned())
+            .arg("--godot-sprite-frames")
+            .arg(hero_a.path())
+            .arg(hero_b.path())
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // the second hero gets a number appended rather than overwriting the first
+        godot_dir.child("hero.tres").assert(predicate::str::contains("margin = Rect2(0, 0, 0, 0)"));
+        godot_dir.child("hero_2.tres").assert(predicate::str::contains("margin = Rect2(24, 31, 88, 53)"));
+        let sprite_frames = godot_dir.child("sprite_frames.tres");
+        sprite_frames.assert(predicate::str::contains("path=\"res://sprites/hero.tres\""));
+        sprite_frames.assert(predicate::str::contains("path=\"res://sprites/hero_2.tres\""));
+    }
+
+    #[test]
+    fn test_godot_output_requires_root_dir() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let godot_dir = temp_dir.child("sprites");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--godot-output")
+            .arg(godot_dir.to_owned())
+            .arg(test_data_path.join("input/input2.png"))
+            .assert();
+        assert
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains("--godot-output requires --output-name-root-dir"));
+        out_image.assert(predicate::path::missing());
+        godot_dir.assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn test_unity_output() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg("--unity-output")
+            .arg("--size-constraint")
+            .arg("any")
             .arg(test_data_path.join("input/input2.png"))
-            .arg(test_data_path.join("input/input3.png"))
             .assert();
         assert
             .success()
             .code(0);
 
-        out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/template_json_hash_file_result/out.png").as_path());
-        out_json.assert(predicate::path::exists());
-        out_json.assert(predicate::path::eq_file(test_data_path.join("results/template_json_hash_file_result/out.json")));
+        // the rect y is measured from the bottom of the 204x204 atlas
+        let out_meta = temp_dir.child("out.png.meta");
+        // the guid is a hash of out.png, the atlas path relative to the project directory
+        out_meta.assert(predicate::str::contains("guid: b84e2d3d30d2f886edeb2af81ae2af3b\n"));
+        out_meta.assert(predicate::str::contains("  spriteMode: 2\n"));
+        out_meta.assert(predicate::str::contains("      name: input2\n      rect:\n        serializedVersion: 2\n        x: 2\n        y: 2\n        width: 200\n        height: 200\n"));
+        out_meta.assert(predicate::str::contains("      spriteID: 87caf4cc50e8f8c8c816ad28699e8d09\n      internalID: 5305247587935581291\n"));
+        out_meta.assert(predicate::str::contains("    nameFileIdTable:\n      input2: 5305247587935581291\n"));
     }
 
     #[test]
-    fn test_toml_template() {
+    fn test_unity_output_requires_root_dir() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let out_image = temp_dir.child("out.png");
-        let out_json = temp_dir.child("out.toml");
+        let out_json = temp_dir.child("out.json");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let assert = cmd
             .arg("--image-output")
             .arg(out_image.to_owned())
             .arg("--meta-output")
             .arg(out_json.to_owned())
-            .arg("--meta-template")
-            .arg("toml")
-            .arg(test_data_path.join("input/input1.png"))
+            .arg("--unity-output")
             .arg(test_data_path.join("input/input2.png"))
-            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains("--unity-output requires --output-name-root-dir"));
+        out_image.assert(predicate::path::missing());
+        temp_dir.child("out.png.meta").assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn test_unity_output_same_names() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let hero_a = temp_dir.child("a/hero.png");
+        hero_a.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        let hero_b = temp_dir.child("b/hero.png");
+        hero_b.write_file(test_data_path.join("input/input1_trim.png").as_path()).unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg("--unity-output")
+            .arg(hero_a.path())
+            .arg(hero_b.path())
             .assert();
         assert
             .success()
             .code(0);
 
-        out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/template_toml_file_result/out.png").as_path());
-        out_json.assert(predicate::path::exists());
-        out_json.assert(predicate::path::eq_file(test_data_path.join("results/template_toml_file_result/out.toml")));
+        // each sprite has its own name and IDs
+        let out_meta = temp_dir.child("out.png.meta");
+        out_meta.assert(predicate::str::contains("      name: hero\n"));
+        out_meta.assert(predicate::str::contains("      name: hero_2\n"));
+        let meta = std::fs::read_to_string(out_meta.path()).unwrap();
+        let sprite_ids: Vec<&str> = meta.lines().filter(|line| line.starts_with("      spriteID: ")).collect();
+        assert_eq!(sprite_ids.len(), 2);
+        assert_ne!(sprite_ids[0], sprite_ids[1]);
+        let internal_ids: Vec<&str> = meta.lines().filter(|line| line.starts_with("      internalID: ")).collect();
+        assert_eq!(internal_ids.len(), 2);
+        assert_ne!(internal_ids[0], internal_ids[1]);
     }
 
     #[test]
-    fn test_yaml_template() {
+    fn test_phaser3_template() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let out_image = temp_dir.child("out.png");
-        let out_json = temp_dir.child("out.yaml");
+        let out_json = temp_dir.child("out.json");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let assert = cmd
             .arg("--image-output")
@@ -280,8 +491,11 @@ mod integration_tests {
             .arg("--meta-output")
             .arg(out_json.to_owned())
             .arg("--meta-template")
-            .arg("yaml")
-            .arg(test_data_path.join("input/input1.png"))
+            .arg("phaser3")
+            .arg("--width")
+            .arg("256")
+            .arg("--height")
+            .arg("256")
             .arg(test_data_path.join("input/input2.png"))
             .arg(test_data_path.join("input/input3.png"))
             .assert();
@@ -289,19 +503,22 @@ mod integration_tests {
             .success()
             .code(0);
 
-        out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/template_yaml_file_result/out.png").as_path());
-        out_json.assert(predicate::path::exists());
-        out_json.assert(predicate::path::eq_file(test_data_path.join("results/template_yaml_file_result/out.yaml")));
+        // each page is a texture with its own frames
+        out_json.assert(predicate::str::contains("\"image\": \"out-0.png\""));
+        out_json.assert(predicate::str::contains("\"image\": \"out-1.png\""));
+        out_json.assert(predicate::str::contains("\"frames\": [\n        {\n          \"filename\": \"input3.png\""));
+        out_json.assert(predicate::str::contains("\"frames\": [\n        {\n          \"filename\": \"input2.png\""));
+        out_json.assert(predicate::str::contains("\"pivot\": {\n            \"x\": 0.5,\n            \"y\": 0.5\n          }"));
+        out_json.assert(predicate::str::contains(format!("\"version\": \"{}\"", env!("CARGO_PKG_VERSION"))));
     }
 
     #[test]
-    fn test_xml_template() {
+    fn test_pixi_template() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let out_image = temp_dir.child("out.png");
-        let out_json = temp_dir.child("out.xml");
+        let out_json = temp_dir.child("out.json");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
         let assert = cmd
             .arg("--image-output")
@@ -309,71 +526,964 @@ mod integration_tests {
             .arg("--meta-output")
             .arg(out_json.to_owned())
             .arg("--meta-template")
-            .arg("xml")
-            .arg(test_data_path.join("input/input1.png"))
-            .arg(test_data_path.join("input/input2.png"))
+            .arg("pixi")
+            .arg("--pivot")
+            .arg("0.5,1")
+            .arg(test_data_path.join("input/input1_trim.png"))
             .arg(test_data_path.join("input/input3.png"))
+            .arg(test_data_path.join("input/input2.png"))
             .assert();
         assert
             .success()
             .code(0);
 
-        out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/template_xml_file_result/out.png").as_path());
-        out_json.assert(predicate::path::exists());
-        out_json.assert(predicate::path::eq_file(test_data_path.join("results/template_xml_file_result/out.xml")));
+        // input2 and input3 are frames of the animation "input", input1_trim has no frame number
+        out_json.assert(predicate::str::contains("\"animations\": {\n    \"input\": [\"input2.png\", \"input3.png\"]\n"));
+        out_json.assert(predicate::str::contains("\"anchor\": {\n        \"x\": 0.5,\n        \"y\": 1\n      }"));
+        out_json.assert(predicate::str::contains("\"format\": \"RGBA8888\""));
+        out_json.assert(predicate::str::contains("\"scale\": \"1\""));
     }
 
     #[test]
-    fn test_non_existent_template_file() {
+    fn test_starling_template() {
+        assert_template_matches_fixture("starling", "out.xml", &["input1_trim.png", "input2.png", "input3.png"], "template_starling_file_result");
+    }
+
+    #[test]
+    fn test_css_template() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
         let out_image = temp_dir.child("out.png");
-        let out_json = temp_dir.child("out.xml");
+        let out_css = temp_dir.child("out.css");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
-        let template_path = test_data_path.join("templates/does-not-exist");
         let assert = cmd
             .arg("--image-output")
             .arg(out_image.to_owned())
             .arg("--meta-output")
-            .arg(out_json.to_owned())
+            .arg(out_css.to_owned())
             .arg("--meta-template")
-            .arg(template_path.to_owned())
-            .arg(test_data_path.join("input/input1.png"))
+            .arg("css")
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg("--retina")
+            .arg(test_data_path.join("input/input1_trim.png"))
             .arg(test_data_path.join("input/input2.png"))
-            .arg(test_data_path.join("input/input3.png"))
             .assert();
         assert
-            .failure()
-            .code(1)
-            .stderr(predicate::str::contains(format!("Error: Template '{}' not found", template_path.display())));
+            .success()
+            .code(0);
+
+        // rotation is disabled as css can't rotate the background
+        out_css.assert(predicate::str::contains(".sprite-input2,\n.sprite-input1_trim {\n\tdisplay: inline-block;\n\tbackground-image: url(\"out.png\");"));
+        out_css.assert(predicate::str::contains("\t\tbackground-image: url(\"out@2x.png\");\n\t\tbackground-size: 256px 512px;"));
+        out_css.assert(predicate::str::contains(".sprite-input1_trim {\n\twidth: 112px;\n\theight: 147px;\n\tbackground-position: -2px -204px;\n}"));
     }
 
     #[test]
-    fn test_broken_template() {
+    fn test_css_template_selectors() {
         let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
         
         let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let spaced = temp_dir.child("my sprite.png");
+        spaced.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        let digit = temp_dir.child("1up.png");
+        digit.write_file(test_data_path.join("input/input3.png").as_path()).unwrap();
         let out_image = temp_dir.child("out.png");
-        let out_json = temp_dir.child("out.xml");
+        let out_css = temp_dir.child("out.css");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_css.to_owned())
+            .arg("--meta-template")
+            .arg("css")
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg(spaced.path())
+            .arg(digit.path())
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // names which aren't valid class names are sanitised
+        out_css.assert(predicate::str::contains(".sprite-my_sprite {\n"));
+        out_css.assert(predicate::str::contains(".sprite-frame_1up {\n"));
+        out_css.assert(predicate::str::contains(".sprite-my sprite").not());
+    }
+
+    #[test]
+    fn test_templates_escape_names() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let awkward = temp_dir.child("a\"b<c&d\\e.png");
+        awkward.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        let custom_template = temp_dir.child("names.xml");
+        custom_template.write_str("{% for frame in frames %}<name>{{ frame.filename }}</name>{% endfor %}").unwrap();
+        let templates = [
+            ("starling", "name=\"a&quot;b&lt;c&amp;d\\e\""),
+            ("cocos2d-plist", "<key>a&quot;b&lt;c&amp;d\\e.png</key>"),
+            ("pixi", "\"a\\\"b<c&d\\\\e.png\": {"),
+            ("phaser3", "\"filename\": \"a\\\"b<c&d\\\\e.png\""),
+            ("c-header", "{ \"a\\\"b<c&d\\\\e.png\", "),
+            ("rust-module", "name: \"a\\\"b<c&d\\\\e.png\", "),
+            // custom templates with an xml extension are escaped automatically
+            (custom_template.path().to_str().unwrap(), "<name>a&quot;b&lt;c&amp;d\\e.png</name>")
+        ];
+        for (template, expected) in templates {
+            let out_image = temp_dir.child("out.png");
+            let out_meta = temp_dir.child("out.txt");
+            let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+            let assert = cmd
+                .arg("--image-output")
+                .arg(out_image.to_owned())
+                .arg("--meta-output")
+                .arg(out_meta.to_owned())
+                .arg("--meta-template")
+                .arg(template)
+                .arg(awkward.path())
+                .assert();
+            assert
+                .success()
+                .code(0);
+            out_meta.assert(predicate::str::contains(expected));
+        }
+    }
+
+    #[test]
+    fn test_godot_output_escapes_names() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let awkward = temp_dir.child("a\"b\\c_0.png");
+        awkward.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let godot_dir = temp_dir.child("sprites");
         let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
-        let template_path = test_data_path.join("templates/broken-no-loop-end");
         let assert = cmd
             .arg("--image-output")
             .arg(out_image.to_owned())
             .arg("--meta-output")
             .arg(out_json.to_owned())
+            .arg("--output-name-root-dir")
+            .arg(temp_dir.path())
+            .arg("--godot-output")
+            .arg(godot_dir.to_owned())
+            .arg("--godot-sprite-frames")
+            .arg(awkward.path())
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        godot_dir.child("sprite_frames.tres").assert(predicate::str::contains("\"name\": &\"a\\\"b\\\\c\",\n"));
+    }
+
+    #[test]
+    fn test_spine_template() {
+        assert_template_matches_fixture("spine", "out.atlas", &["input1_trim.png", "input2.png", "input3.png"], "template_spine_file_result");
+    }
+
+    #[test]
+    fn test_dragonbones_template() {
+        assert_template_matches_fixture("dragonbones", "out.json", &["input1_trim.png", "input2.png", "input3.png"], "template_dragonbones_file_result");
+    }
+
+    #[test]
+    fn test_c_header_template() {
+        assert_template_matches_fixture("c-header", "out.h", &["input1_trim.png", "input2.png", "input3.png"], "template_c_header_file_result");
+    }
+
+    #[test]
+    fn test_rust_module_template() {
+        assert_template_matches_fixture("rust-module", "out.rs", &["input1_trim.png", "input2.png", "input3.png"], "template_rust_module_file_result");
+    }
+
+    #[test]
+    fn test_msgpack_output() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_msgpack = temp_dir.child("out.msgpack");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_msgpack.to_owned())
             .arg("--meta-template")
-            .arg(template_path.to_owned())
-            .arg(test_data_path.join("input/input1.png"))
+            .arg("msgpack")
+            .arg(test_data_path.join("input/input2.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // a map of meta, pages, frames, animations and retina
+        let data = std::fs::read(out_msgpack.path()).unwrap();
+        assert_eq!(&data[0..6], b"\x85\xa4meta");
+        assert!(data.windows(20).any(|window| window == b"\xa8filename\xaainput2.png"));
+    }
+
+    #[test]
+    fn test_binary_output() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_bin = temp_dir.child("out.bin");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_bin.to_owned())
+            .arg("--meta-template")
+            .arg("binary")
             .arg(test_data_path.join("input/input2.png"))
             .arg(test_data_path.join("input/input3.png"))
             .assert();
+        assert
+            .success()
+            .code(0);
+
+        let data = std::fs::read(out_bin.path()).unwrap();
+        let read = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        // header, then 1 page, 2 frames and the string table
+        assert_eq!(&data[0..4], b"ATLB");
+        assert_eq!([read(4), read(8), read(12)], [1, 1, 2]);
+        assert_eq!(data.len(), 20 + 12 + 56 * 2 + read(16) as usize);
+        // first frame is 2,2 200x200
+        assert_eq!([read(44), read(48), read(52), read(56)], [2, 2, 200, 200]);
+        assert!(data.ends_with(b"out.png\0input3.png\0input2.png\0") || data.ends_with(b"out.png\0input2.png\0input3.png\0"));
+    }
+
+    #[test]
+    fn test_multiple_pages() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--width")
+            .arg("256")
+            .arg("--height")
+            .arg("256")
+            .arg(test_data_path.join("input/input1.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_image.assert(predicate::path::missing());
+        temp_dir.child("out-0.png").assert(predicate::path::exists());
+        temp_dir.child("out-1.png").assert(predicate::path::exists());
+        temp_dir.child("out-2.png").assert(predicate::path::exists());
+        temp_dir.child("out-3.png").assert(predicate::path::missing());
+        // json-hash describes a single image, so each page has its own meta data
+        out_json.assert(predicate::path::missing());
+        temp_dir.child("out-0.json").assert(predicate::str::contains("\"image\": \"out-0.png\""));
+        temp_dir.child("out-1.json").assert(predicate::str::contains("\"image\": \"out-1.png\""));
+        temp_dir.child("out-2.json").assert(predicate::str::contains("\"image\": \"out-2.png\""));
+        temp_dir.child("out-3.json").assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn test_multiple_pages_templates() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        let inputs = [ "input1", "input2", "input3" ];
+        let pages = [ "out-0.png", "out-1.png", "out-2.png" ];
+        let run = |template: &str, temp_dir: &assert_fs::TempDir| {
+            let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+            cmd
+                .arg("--image-output")
+                .arg(temp_dir.child("out.png").path())
+                .arg("--meta-output")
+                .arg(temp_dir.child("out.meta").path())
+                .arg("--meta-template")
+                .arg(template)
+                .arg("--width")
+                .arg("256")
+                .arg("--height")
+                .arg("256")
+                .args(inputs.map(|input| test_data_path.join(format!("input/{}.png", input))))
+                .assert()
+                .success()
+                .code(0);
+        };
+        let mentioned = |contents: &str, names: &[&str]| -> Vec<String> {
+            names.iter().filter(|name| contents.to_lowercase().contains(*name)).map(|name| name.to_string()).collect()
+        };
+
+        // each sub image needs a page of its own. Templates describing a single image are output once per page, with only
+        // the frames on that page
+        for template in [ "json-hash", "json-array", "xml", "toml", "yaml", "pixi", "starling", "cocos2d-plist", "dragonbones" ] {
+            let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+            run(template, &temp_dir);
+            temp_dir.child("out.meta").assert(predicate::path::missing());
+            let mut frames: Vec<String> = vec!();
+            for ( page_index, page ) in pages.iter().enumerate() {
+                let contents = std::fs::read_to_string(temp_dir.child(format!("out-{}.meta", page_index)).path()).unwrap();
+                assert_eq!(mentioned(&contents, &pages), vec![ page.to_string() ], "{} page {}", template, page_index);
+                let page_frames = mentioned(&contents, &inputs);
+                assert_eq!(page_frames.len(), 1, "{} page {}", template, page_index);
+                frames.extend(page_frames);
+            }
+            frames.sort();
+            assert_eq!(frames, inputs, "{}", template);
+        }
+
+        // templates which list every page
+        for template in [ "libgdx", "spine", "phaser3", "css", "c-header", "rust-module" ] {
+            let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+            run(template, &temp_dir);
+            temp_dir.child("out-0.meta").assert(predicate::path::missing());
+            let contents = std::fs::read_to_string(temp_dir.child("out.meta").path()).unwrap();
+            assert_eq!(mentioned(&contents, &pages), pages, "{}", template);
+            assert_eq!(mentioned(&contents, &inputs), inputs, "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_multiple_pages_custom_template() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        let run = |all_pages: bool, temp_dir: &assert_fs::TempDir| {
+            let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+            cmd
+                .arg("--image-output")
+                .arg(temp_dir.child("out.png").path())
+                .arg("--meta-output")
+                .arg(temp_dir.child("out.txt").path())
+                .arg("--meta-template")
+                .arg(test_data_path.join("templates/pages"))
+                .arg("--width")
+                .arg("256")
+                .arg("--height")
+                .arg("256")
+                .args(if all_pages { vec!["--meta-all-pages"] } else { vec![] })
+                .arg(test_data_path.join("input/input1.png"))
+                .arg(test_data_path.join("input/input2.png"))
+                .arg(test_data_path.join("input/input3.png"))
+                .assert()
+                .success()
+                .code(0);
+        };
+
+        // custom templates are output once per page by default, even when they use pages
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        run(false, &temp_dir);
+        temp_dir.child("out.txt").assert(predicate::path::missing());
+        for page_index in 0..3 {
+            let page = temp_dir.child(format!("out-{}.txt", page_index));
+            page.assert(predicate::str::is_match(format!("^out-{}.png: input[123].png\n$", page_index)).unwrap());
+        }
+
+        // unless they list every page themselves
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        run(true, &temp_dir);
+        temp_dir.child("out-0.txt").assert(predicate::path::missing());
+        temp_dir.child("out.txt").assert(predicate::str::is_match("^out-0.png: input[123].png\nout-1.png: input[123].png\nout-2.png: input[123].png\n$").unwrap());
+    }
+
+    #[test]
+    fn test_optimize() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--optimize")
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0)
+            .stdout(predicate::str::contains("Optimised packing using maxrects heuristic"));
+
+        out_image.assert(predicate::path::exists());
+        out_json.assert(predicate::path::exists());
+    }
+
+    #[test]
+    fn test_skyline_algorithm() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--algorithm")
+            .arg("skyline")
+            .arg("--skyline-heuristic")
+            .arg("min-waste")
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_image.assert(predicate::path::exists());
+        out_json.assert(predicate::path::exists());
+        out_json.assert(predicate::str::contains("\"input1_trim.png\""));
+    }
+
+    #[test]
+    fn test_guillotine_algorithm() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--algorithm")
+            .arg("guillotine")
+            .arg("--guillotine-heuristic")
+            .arg("best-short-side-fit")
+            .arg("--guillotine-split")
+            .arg("min-area")
+            .arg("--guillotine-merge")
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .arg(test_data_path.join("input/input4.png"))
+            .arg(test_data_path.join("input/input5.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_image.assert(predicate::path::exists());
+		assert!(are_pngs_equal(out_image.path(), test_data_path.join("results/guillotine_file_result/out.png").as_path()));
+        out_json.assert(predicate::path::exists());
+        out_json.assert(predicate::path::eq_file(test_data_path.join("results/guillotine_file_result/out.json")));
+    }
+
+    #[test]
+    fn test_duplicate_images() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let duplicate = temp_dir.child("copy.png");
+        duplicate.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/aliases"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .arg(duplicate.path())
+            .assert();
+        assert
+            .success()
+            .code(0)
+            .stdout(predicate::str::contains("is a duplicate of"));
+
+        out_image.assert(predicate::path::exists());
+        out_txt.assert(predicate::str::contains("input2.png 2,204 aliases copy.png\n"));
+        out_txt.assert(predicate::str::contains("copy.png 2,204 alias_of input2.png\n"));
+    }
+
+    #[test]
+    fn test_duplicate_nine_patch_images() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        // 10x10 red panel surrounded by Android nine-patch guides, and an identical copy
+        let nine_patch = temp_dir.child("panel.9.png");
+        let mut image = image::RgbaImage::from_pixel(12, 12, image::Rgba([255, 0, 0, 255]));
+        for i in 0..12 {
+            image.put_pixel(i, 0, image::Rgba([0, 0, 0, 0]));
+            image.put_pixel(0, i, image::Rgba([0, 0, 0, 0]));
+            image.put_pixel(i, 11, image::Rgba([0, 0, 0, 0]));
+            image.put_pixel(11, i, image::Rgba([0, 0, 0, 0]));
+        }
+        image.put_pixel(5, 0, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(0, 5, image::Rgba([0, 0, 0, 255]));
+        image.save(nine_patch.path()).unwrap();
+        let duplicate = temp_dir.child("dup.9.png");
+        duplicate.write_file(nine_patch.path()).unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/aliases"))
+            .arg(nine_patch.path())
+            .arg(duplicate.path())
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // aliases refer to the original by its name without the .9 suffix
+        out_txt.assert(predicate::str::contains("panel.png 2,2 aliases dup.png\n"));
+        out_txt.assert(predicate::str::contains("dup.png 2,2 alias_of panel.png\n"));
+    }
+
+    #[test]
+    fn test_extrude() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--extrude")
+            .arg("4")
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_image.assert(predicate::path::exists());
+        // sub images are offset by the padding plus the extruded border
+        out_json.assert(predicate::str::contains("\"x\": 6,"));
+        out_json.assert(predicate::str::contains("\"y\": 6,"));
+    }
+
+    #[test]
+    fn test_border_and_shape_padding() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/aliases"))
+            .arg("--border-padding")
+            .arg("0")
+            .arg("--shape-padding")
+            .arg("5")
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_txt.assert(predicate::str::contains(" 0,0\n"));
+        out_txt.assert(predicate::str::contains(" 0,205\n"));
+    }
+
+    #[test]
+    fn test_size_constraint_any() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--size-constraint")
+            .arg("any")
+            .arg(test_data_path.join("input/input2.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        out_image.assert(predicate::path::exists());
+        // 200x200 sub image with 2 pixels of padding on each side
+        out_json.assert(predicate::str::contains("\"w\": 204,"));
+        out_json.assert(predicate::str::contains("\"h\": 204\n"));
+    }
+
+    #[test]
+    fn test_trim_margin() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--trim-margin")
+            .arg("4")
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // trimmed offset moves 4 pixels up and left of the opaque pixels
+        out_json.assert(predicate::str::contains("\"x\": 20,"));
+        out_json.assert(predicate::str::contains("\"y\": 27,"));
+    }
+
+    #[test]
+    fn test_trim_mode_crop() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--trim-mode")
+            .arg("crop")
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // the source size is the cropped size, with no offset
+        out_json.assert(predicate::str::contains("\"trimmed\": false"));
+        out_json.assert(predicate::str::contains("\"sourceSize\": {\n        \"w\": 112,\n        \"h\": 147\n"));
+        out_json.assert(predicate::str::contains("\"x\": 0,"));
+    }
+
+    #[test]
+    fn test_skip_empty_image() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let empty_image = temp_dir.child("empty.png");
+        image::RgbaImage::new(16, 16).save(empty_image.path()).unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.json");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--skip-empty")
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(empty_image.path())
+            .assert();
+        assert
+            .success()
+            .code(0)
+            .stderr(predicate::str::contains("Warning: Skipping empty sub image"));
+
+        out_json.assert(predicate::str::contains("input2.png"));
+        out_json.assert(predicate::str::contains("empty.png").not());
+    }
+
+    #[test]
+    fn test_polygon_outlines() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/polygons"))
+            .arg("--polygon")
+            .arg("--polygon-max-vertices")
+            .arg("6")
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // fully opaque sub images are outlined by their rect
+        out_txt.assert(predicate::str::contains("input2.png vertices=4 triangles=2 first=[0.0,0.0]\n"));
+        out_txt.assert(predicate::str::contains("input1_trim.png vertices=6 triangles=4"));
+    }
+
+    #[test]
+    fn test_pivots() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let sprite = temp_dir.child("sprite.png");
+        sprite.write_file(test_data_path.join("input/input1_trim.png").as_path()).unwrap();
+        temp_dir.child("sprite.png.toml").write_str("pivot = \"100px,200px\"\n").unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/pivots"))
+            .arg("--pivot")
+            .arg("0,1")
+            .arg("--rotation-disable")
+            .arg(sprite.path())
+            .arg(test_data_path.join("input/input2.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // default pivot from the command line
+        out_txt.assert(predicate::str::contains("input2.png pivot=0,1 frame=0,200\n"));
+        // pixel pivot from the sidecar file, relative to the trimmed frame at 24,31
+        out_txt.assert(predicate::str::contains("sprite.png pivot=0.5,1 frame=76,169\n"));
+    }
+
+    #[test]
+    fn test_animations() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        for name in ["run_0010.png", "run_0002.png", "run_0001.png", "jump-f2.png", "jump-f1.png"] {
+            temp_dir.child(name).write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        }
+        temp_dir.child("run_0002.png.toml").write_str("fps = 12\nloop = false\n").unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/animations"))
+            .arg("--animation-regex")
+            .arg("^(.*?)[-_]f?([0-9]+)$")
+            .arg("--animation-fps")
+            .arg("8")
+            .arg("--duplicates-disable")
+            .arg(temp_dir.child("run_0010.png").path())
+            .arg(temp_dir.child("run_0002.png").path())
+            .arg(temp_dir.child("run_0001.png").path())
+            .arg(temp_dir.child("jump-f2.png").path())
+            .arg(temp_dir.child("jump-f1.png").path())
+            .arg(test_data_path.join("input/input1_trim.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // frames are ordered by number, and sub images without a frame number aren't animations
+        out_txt.assert("jump frames=jump-f1.png,jump-f2.png fps=8 loop=true\nrun frames=run_0001.png,run_0002.png,run_0010.png fps=12 loop=false\n");
+    }
+
+    #[test]
+    fn test_nine_slices() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        // 10x10 red panel surrounded by Android nine-patch guides
+        let nine_patch = temp_dir.child("panel.9.png");
+        let mut image = image::RgbaImage::from_pixel(12, 12, image::Rgba([255, 0, 0, 255]));
+        for i in 0..12 {
+            image.put_pixel(i, 0, image::Rgba([0, 0, 0, 0]));
+            image.put_pixel(0, i, image::Rgba([0, 0, 0, 0]));
+            image.put_pixel(i, 11, image::Rgba([0, 0, 0, 0]));
+            image.put_pixel(11, i, image::Rgba([0, 0, 0, 0]));
+        }
+        for x in 4..8 {
+            image.put_pixel(x, 0, image::Rgba([0, 0, 0, 255]));
+        }
+        for y in 3..9 {
+            image.put_pixel(0, y, image::Rgba([0, 0, 0, 255]));
+        }
+        image.save(nine_patch.path()).unwrap();
+        let sidecar = temp_dir.child("sidecar.png");
+        sidecar.write_file(test_data_path.join("input/input2.png").as_path()).unwrap();
+        temp_dir.child("sidecar.png.json").write_str("{ \"nine_slice\": [ 1, 2, 3, 4 ] }").unwrap();
+        let out_image = temp_dir.child("out.png");
+        let out_txt = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_txt.to_owned())
+            .arg("--meta-template")
+            .arg(test_data_path.join("templates/nine_slices"))
+            .arg(nine_patch.path())
+            .arg(sidecar.path())
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .success()
+            .code(0);
+
+        // guides are stripped from the image and the .9 suffix from its name
+        out_txt.assert(predicate::str::contains("panel.png 10x10 nine_slice=3,2,3,2\n"));
+        out_txt.assert(predicate::str::contains("sidecar.png 200x200 nine_slice=1,2,3,4\n"));
+        out_txt.assert(predicate::str::contains("input3.png 200x200\n"));
+    }
+
+    #[test]
+    fn test_non_existent_template_file() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.xml");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let template_path = test_data_path.join("templates/does-not-exist");
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--meta-template")
+            .arg(&template_path)
+            .arg(test_data_path.join("input/input1.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains(format!("Error: Template '{}' not found", template_path.display())));
+    }
+
+    #[test]
+    fn test_broken_template() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.xml");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let template_path = test_data_path.join("templates/broken-no-loop-end");
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--meta-template")
+            .arg(&template_path)
+            .arg(test_data_path.join("input/input1.png"))
+            .arg(test_data_path.join("input/input2.png"))
+            .arg(test_data_path.join("input/input3.png"))
+            .assert();
+        assert
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains(format!("Error: Meta template {:?} line 30, column 1:", template_path.display().to_string())));
+        // the template is checked before packing
+        out_image.assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn test_template_missing_variable() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        let template_path = test_data_path.join("templates/broken-missing-variable");
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--meta-template")
+            .arg(&template_path)
+            .arg(test_data_path.join("input/input1.png"))
+            .assert();
+        assert
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains(format!("Error: Meta template {:?} line 3, column 14 (approximate): Variable `frame.pretrimed_w` not found", template_path.display().to_string())));
+        out_image.assert(predicate::path::missing());
+        out_json.assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn test_template_unguarded_nine_slice() {
+        let test_data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_fixtures");
+        
+        let temp_dir = assert_fs::TempDir::new().unwrap()/*.into_persistent()*/;
+        let out_image = temp_dir.child("out.png");
+        let out_json = temp_dir.child("out.txt");
+        let mut cmd = Command::cargo_bin("atlasbuilder").unwrap();
+        // nine_slice is only set for nine-patch images, so a template using it without checking fails
+        let template_path = test_data_path.join("templates/broken-unguarded-nine-slice");
+        let assert = cmd
+            .arg("--image-output")
+            .arg(out_image.to_owned())
+            .arg("--meta-output")
+            .arg(out_json.to_owned())
+            .arg("--meta-template")
+            .arg(&template_path)
+            .arg(test_data_path.join("input/input1.png"))
+            .assert();
         assert
             .failure()
             .code(1)
-            .stderr(predicate::str::contains("Error: Failed to parse '__tera_one_off'"));
+            .stderr(predicate::str::contains("Variable `frame.nine_slice.left` not found"));
+        out_image.assert(predicate::path::missing());
     }
 
     #[test]
@@ -391,7 +1501,7 @@ mod integration_tests {
             .arg("--meta-output")
             .arg(out_json.to_owned())
             .arg("--meta-template")
-            .arg(template_path.to_owned())
+            .arg(&template_path)
             .arg(test_data_path.join("input/input1.png"))
             .arg(test_data_path.join("input/input2.png"))
             .arg(test_data_path.join("input/input3.png"))
@@ -401,7 +1511,7 @@ mod integration_tests {
             .code(0);
 
         out_image.assert(predicate::path::exists());
-		are_pngs_equal(out_image.path(), test_data_path.join("results/template_json_hash_file_result/out.png").as_path());
+		assert!(are_pngs_equal(out_image.path(), test_data_path.join("results/template_json_hash_file_result/out.png").as_path()));
         out_json.assert(predicate::path::exists());
         out_json.assert(predicate::path::eq_file(test_data_path.join("results/template_json_hash_file_result/out.json")));
 